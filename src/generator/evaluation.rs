@@ -0,0 +1,274 @@
+use crate::model::{
+    board::{Board, TO_BOARD},
+    piece::{Color, Piece},
+};
+
+/**
+ * Piece-square tables, from white's point of view with a8 at index 0.
+ * Black reads them mirrored vertically
+ */
+#[rustfmt::skip]
+const PAWN_MIDDLEGAME: [i32; 64] = [
+      0,   0,   0,   0,   0,   0,   0,   0,
+     50,  50,  50,  50,  50,  50,  50,  50,
+     10,  10,  20,  30,  30,  20,  10,  10,
+      5,   5,  10,  25,  25,  10,   5,   5,
+      0,   0,   0,  20,  20,   0,   0,   0,
+      5,  -5, -10,   0,   0, -10,  -5,   5,
+      5,  10,  10, -20, -20,  10,  10,   5,
+      0,   0,   0,   0,   0,   0,   0,   0,
+];
+
+#[rustfmt::skip]
+const PAWN_ENDGAME: [i32; 64] = [
+      0,   0,   0,   0,   0,   0,   0,   0,
+     80,  80,  80,  80,  80,  80,  80,  80,
+     50,  50,  50,  50,  50,  50,  50,  50,
+     30,  30,  30,  30,  30,  30,  30,  30,
+     20,  20,  20,  20,  20,  20,  20,  20,
+     10,  10,  10,  10,  10,  10,  10,  10,
+      0,   0,   0,   0,   0,   0,   0,   0,
+      0,   0,   0,   0,   0,   0,   0,   0,
+];
+
+#[rustfmt::skip]
+const KNIGHT: [i32; 64] = [
+    -50, -40, -30, -30, -30, -30, -40, -50,
+    -40, -20,   0,   0,   0,   0, -20, -40,
+    -30,   0,  10,  15,  15,  10,   0, -30,
+    -30,   5,  15,  20,  20,  15,   5, -30,
+    -30,   0,  15,  20,  20,  15,   0, -30,
+    -30,   5,  10,  15,  15,  10,   5, -30,
+    -40, -20,   0,   5,   5,   0, -20, -40,
+    -50, -40, -30, -30, -30, -30, -40, -50,
+];
+
+#[rustfmt::skip]
+const BISHOP: [i32; 64] = [
+    -20, -10, -10, -10, -10, -10, -10, -20,
+    -10,   0,   0,   0,   0,   0,   0, -10,
+    -10,   0,   5,  10,  10,   5,   0, -10,
+    -10,   5,   5,  10,  10,   5,   5, -10,
+    -10,   0,  10,  10,  10,  10,   0, -10,
+    -10,  10,  10,  10,  10,  10,  10, -10,
+    -10,   5,   0,   0,   0,   0,   5, -10,
+    -20, -10, -10, -10, -10, -10, -10, -20,
+];
+
+#[rustfmt::skip]
+const ROOK: [i32; 64] = [
+      0,   0,   0,   0,   0,   0,   0,   0,
+      5,  10,  10,  10,  10,  10,  10,   5,
+     -5,   0,   0,   0,   0,   0,   0,  -5,
+     -5,   0,   0,   0,   0,   0,   0,  -5,
+     -5,   0,   0,   0,   0,   0,   0,  -5,
+     -5,   0,   0,   0,   0,   0,   0,  -5,
+     -5,   0,   0,   0,   0,   0,   0,  -5,
+      0,   0,   0,   5,   5,   0,   0,   0,
+];
+
+#[rustfmt::skip]
+const QUEEN: [i32; 64] = [
+    -20, -10, -10,  -5,  -5, -10, -10, -20,
+    -10,   0,   0,   0,   0,   0,   0, -10,
+    -10,   0,   5,   5,   5,   5,   0, -10,
+     -5,   0,   5,   5,   5,   5,   0,  -5,
+      0,   0,   5,   5,   5,   5,   0,  -5,
+    -10,   5,   5,   5,   5,   5,   0, -10,
+    -10,   0,   5,   0,   0,   0,   0, -10,
+    -20, -10, -10,  -5,  -5, -10, -10, -20,
+];
+
+#[rustfmt::skip]
+const KING_MIDDLEGAME: [i32; 64] = [
+    -30, -40, -40, -50, -50, -40, -40, -30,
+    -30, -40, -40, -50, -50, -40, -40, -30,
+    -30, -40, -40, -50, -50, -40, -40, -30,
+    -30, -40, -40, -50, -50, -40, -40, -30,
+    -20, -30, -30, -40, -40, -30, -30, -20,
+    -10, -20, -20, -20, -20, -20, -20, -10,
+     20,  20,   0,   0,   0,   0,  20,  20,
+     20,  30,  10,   0,   0,  10,  30,  20,
+];
+
+#[rustfmt::skip]
+const KING_ENDGAME: [i32; 64] = [
+    -50, -40, -30, -20, -20, -30, -40, -50,
+    -30, -20, -10,   0,   0, -10, -20, -30,
+    -30, -10,  20,  30,  30,  20, -10, -30,
+    -30, -10,  30,  40,  40,  30, -10, -30,
+    -30, -10,  30,  40,  40,  30, -10, -30,
+    -30, -10,  20,  30,  30,  20, -10, -30,
+    -30, -30,   0,   0,   0,   0, -30, -30,
+    -50, -30, -30, -30, -30, -30, -30, -50,
+];
+
+/**
+ * Phase of a board with all its pieces, it decreases to 0 as pieces get exchanged
+ */
+const OPENING_PHASE: i32 = 24;
+
+/**
+ * Value used to order captures in the search
+ */
+pub fn piece_value(piece: &Piece) -> i32 {
+    match piece {
+        Piece::Pawn { .. } => 100,
+        Piece::Knight { .. } => 320,
+        Piece::Bishop { .. } => 330,
+        Piece::Rook { .. } => 500,
+        Piece::Queen { .. } => 900,
+        Piece::King { .. } => 0,
+    }
+}
+
+/**
+ * Material of the piece in the middlegame and in the endgame
+ */
+fn material(piece: &Piece) -> (i32, i32) {
+    match piece {
+        Piece::Pawn { .. } => (82, 94),
+        Piece::Knight { .. } => (337, 281),
+        Piece::Bishop { .. } => (365, 297),
+        Piece::Rook { .. } => (477, 512),
+        Piece::Queen { .. } => (1025, 936),
+        Piece::King { .. } => (0, 0),
+    }
+}
+
+fn tables(piece: &Piece) -> (&'static [i32; 64], &'static [i32; 64]) {
+    match piece {
+        Piece::Pawn { .. } => (&PAWN_MIDDLEGAME, &PAWN_ENDGAME),
+        Piece::Knight { .. } => (&KNIGHT, &KNIGHT),
+        Piece::Bishop { .. } => (&BISHOP, &BISHOP),
+        Piece::Rook { .. } => (&ROOK, &ROOK),
+        Piece::Queen { .. } => (&QUEEN, &QUEEN),
+        Piece::King { .. } => (&KING_MIDDLEGAME, &KING_ENDGAME),
+    }
+}
+
+fn phase_weight(piece: &Piece) -> i32 {
+    match piece {
+        Piece::Knight { .. } | Piece::Bishop { .. } => 1,
+        Piece::Rook { .. } => 2,
+        Piece::Queen { .. } => 4,
+        Piece::Pawn { .. } | Piece::King { .. } => 0,
+    }
+}
+
+/**
+ * Middlegame and endgame score of a piece on a mailbox square, positive for white
+ */
+pub fn piece_square_score(piece: &Piece, position: usize) -> (i32, i32) {
+    let square = TO_BOARD[position] as usize;
+    let (material_middlegame, material_endgame) = material(piece);
+    let (middlegame, endgame) = tables(piece);
+    match piece.get_color() {
+        Color::WHITE => (
+            material_middlegame + middlegame[square],
+            material_endgame + endgame[square],
+        ),
+        Color::BLACK => (
+            -material_middlegame - middlegame[square ^ 56],
+            -material_endgame - endgame[square ^ 56],
+        ),
+    }
+}
+
+/**
+ * From OPENING_PHASE with all the pieces on the board, to 0 with only kings and pawns
+ */
+pub fn game_phase(board: &Board) -> i32 {
+    board
+        .pieces_iter()
+        .map(|(_, piece)| phase_weight(piece))
+        .sum::<i32>()
+        .min(OPENING_PHASE)
+}
+
+/**
+ * Static score of the board in centipawns, from the point of view of the color to play.
+ * Material and piece-square scores are tapered between the middlegame and the endgame
+ */
+pub fn evaluate(board: &Board) -> i32 {
+    let (middlegame, endgame) = board
+        .pieces_iter()
+        .map(|(position, piece)| piece_square_score(piece, position))
+        .fold(
+            (0, 0),
+            |(middlegame, endgame), (piece_middlegame, piece_endgame)| {
+                (middlegame + piece_middlegame, endgame + piece_endgame)
+            },
+        );
+    let phase = game_phase(board);
+    let score = (middlegame * phase + endgame * (OPENING_PHASE - phase)) / OPENING_PHASE;
+    match board.color_turn() {
+        Color::WHITE => score,
+        Color::BLACK => -score,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{evaluate, game_phase, OPENING_PHASE};
+    use crate::model::{board::START_FEN, fixtures::board};
+
+    /**
+     * Same position with the ranks flipped and the colors of the pieces swapped, the same side
+     * to play
+     */
+    fn mirror(fen: &str) -> String {
+        let mut fields = fen.split(' ');
+        let placement: Vec<String> = fields
+            .next()
+            .unwrap()
+            .split('/')
+            .rev()
+            .map(|rank| {
+                rank.chars()
+                    .map(|c| {
+                        if c.is_ascii_uppercase() {
+                            c.to_ascii_lowercase()
+                        } else {
+                            c.to_ascii_uppercase()
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        let turn = fields.next().unwrap();
+        format!("{} {} - - 0 1", placement.join("/"), turn)
+    }
+
+    #[test]
+    fn scores_the_start_position_even() {
+        assert_eq!(evaluate(&board(START_FEN)), 0);
+        assert_eq!(game_phase(&board(START_FEN)), OPENING_PHASE);
+    }
+
+    #[test]
+    fn negates_the_score_of_mirrored_positions() {
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 b - - 12 40",
+            "rnbqkb1r/pp1p1ppp/5n2/2p1p3/4P3/2N2N2/PPPP1PPP/R1BQKB1R w KQkq - 0 4",
+        ] {
+            let score = evaluate(&board(fen));
+            assert_ne!(score, 0, "{}", fen);
+            assert_eq!(evaluate(&board(&mirror(fen))), -score, "{}", fen);
+        }
+    }
+
+    #[test]
+    fn uses_the_endgame_tables_without_pieces() {
+        // The white king on d4 gets 40 in the endgame against -40 in the middlegame, the black
+        // king on e8 loses 30 in the endgame and nothing in the middlegame
+        let kings = board("4k3/8/8/8/3K4/8/8/8 w - - 0 1");
+        assert_eq!(game_phase(&kings), 0);
+        assert_eq!(evaluate(&kings), 70);
+
+        let pawns = board("4k3/8/8/8/3K4/8/P7/8 b - - 0 1");
+        assert_eq!(game_phase(&pawns), 0);
+        assert_eq!(evaluate(&pawns), -70 - 94);
+    }
+}
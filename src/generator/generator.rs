@@ -1,31 +1,178 @@
-use crate::model::{actions, board::Board};
+use crate::model::{actions, position::Position};
 
-pub fn count_actions(board: &mut Board, depth: u32, is_start: bool) -> u32 {
+/**
+ * Number of leaf nodes at the given depth. The divide is printed first when `is_start` is set
+ */
+pub fn count_actions<P: Position>(position: &mut P, depth: u32, is_start: bool) -> u64 {
     if depth == 0 {
         return 1;
     }
-    let mut moves = actions::generate_moves(board);
+    if is_start {
+        let divide = divide(position, depth);
+        for (notation, count) in divide.iter() {
+            println!("{} : {}", notation, count);
+        }
+        return divide.iter().map(|(_, count)| count).sum();
+    }
     let mut count = 0;
-    let mut target = String::from("_");
-    let mut start = String::from("_");
-    while let Some(mv) = moves.pop() {
-        if is_start {
-            start = String::from_iter(vec![
-                Board::get_file(mv.start_square()),
-                Board::get_column(mv.start_square()),
-            ]);
-            target = String::from_iter(vec![
-                Board::get_file(mv.target_square()),
-                Board::get_column(mv.target_square()),
-            ]);
+    for action in actions::generate_moves(position) {
+        position.play(action);
+        count += count_actions(position, depth - 1, false);
+        position.undo();
+    }
+    count
+}
+
+/**
+ * Node count below each legal move, sorted by long algebraic notation.
+ * Empty at depth 0, where the position itself is the only node
+ */
+pub fn divide<P: Position>(position: &mut P, depth: u32) -> Vec<(String, u64)> {
+    if depth == 0 {
+        return Vec::new();
+    }
+    let mut divide: Vec<(String, u64)> = actions::generate_moves(position)
+        .into_iter()
+        .map(|action| {
+            let notation = position.action_key(&action).to_string();
+            position.play(action);
+            let count = count_actions(position, depth - 1, false);
+            position.undo();
+            (notation, count)
+        })
+        .collect();
+    divide.sort();
+    divide
+}
+
+/**
+ * Node counts from https://www.chessprogramming.org/Perft_Results
+ */
+#[cfg(test)]
+mod tests {
+    use super::{count_actions, divide};
+    use crate::model::{
+        bitboard::BitboardPosition,
+        board::{Board, START_FEN},
+        position::Position,
+    };
+
+    const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+    const POSITION_3: &str = "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1";
+    const POSITION_4: &str = "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1";
+    const POSITION_4_MIRRORED: &str =
+        "r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1";
+    const POSITION_5: &str = "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8";
+    const POSITION_6: &str =
+        "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10";
+
+    /**
+     * Checks the counts from depth 1 with both backends, and that the board is left as it was
+     */
+    fn assert_perft(fen: &str, expected: &[u64]) {
+        let mut board = match Board::from_fen(fen.to_string()) {
+            Ok(board) => board,
+            Err(e) => panic!("Invalid board {}", e.err),
+        };
+        let hash = board.hash();
+        for (depth, expected) in (1..).zip(expected) {
+            assert_eq!(
+                count_actions(&mut board, depth, false),
+                *expected,
+                "perft({}) of {}",
+                depth,
+                fen
+            );
         }
-        board.do_move(mv);
-        let actions = count_actions(board, depth - 1, false);
-        if is_start {
-            println!("{}{} : {}", start, target, actions)
+        assert_eq!(board.to_fen(), fen);
+        assert_eq!(board.hash(), hash);
+
+        let mut position = match BitboardPosition::from_fen(fen) {
+            Ok(position) => position,
+            Err(e) => panic!("Invalid board {}", e.err),
+        };
+        for (depth, expected) in (1..).zip(expected) {
+            assert_eq!(
+                count_actions(&mut position, depth, false),
+                *expected,
+                "bitboard perft({}) of {}",
+                depth,
+                fen
+            );
         }
-        count += actions;
-        board.undo_last_move()
+        assert_eq!(position.to_fen(), fen);
+    }
+
+    /**
+     * Both backends give the same node count below each move
+     */
+    fn assert_same_divide(fen: &str, depth: u32) {
+        let mut board = Board::from_fen(fen.to_string()).unwrap_or_else(|e| panic!("{}", e.err));
+        let mut position = BitboardPosition::from_fen(fen).unwrap_or_else(|e| panic!("{}", e.err));
+        assert_eq!(
+            divide(&mut board, depth),
+            divide(&mut position, depth),
+            "divide({}) of {}",
+            depth,
+            fen
+        );
+    }
+
+    #[test]
+    fn start_position() {
+        assert_perft(START_FEN, &[20, 400, 8902, 197281]);
+    }
+
+    #[test]
+    fn kiwipete() {
+        assert_perft(KIWIPETE, &[48, 2039, 97862]);
+    }
+
+    #[test]
+    fn position_3() {
+        assert_perft(POSITION_3, &[14, 191, 2812, 43238, 674624]);
+    }
+
+    #[test]
+    fn position_4() {
+        assert_perft(POSITION_4, &[6, 264, 9467, 422333]);
+    }
+
+    #[test]
+    fn position_4_mirrored() {
+        assert_perft(POSITION_4_MIRRORED, &[6, 264, 9467, 422333]);
+    }
+
+    #[test]
+    fn position_5() {
+        assert_perft(POSITION_5, &[44, 1486, 62379]);
+    }
+
+    #[test]
+    fn position_6() {
+        assert_perft(POSITION_6, &[46, 2079, 89890]);
+    }
+
+    #[test]
+    fn backends_agree() {
+        for fen in [
+            START_FEN, KIWIPETE, POSITION_3, POSITION_4, POSITION_5, POSITION_6,
+        ] {
+            assert_same_divide(fen, 3);
+        }
+    }
+
+    /**
+     * Deeper counts, run with `cargo test --release -- --ignored`
+     */
+    #[test]
+    #[ignore]
+    fn deep() {
+        assert_perft(START_FEN, &[20, 400, 8902, 197281, 4865609]);
+        assert_perft(KIWIPETE, &[48, 2039, 97862, 4085603]);
+        assert_perft(POSITION_3, &[14, 191, 2812, 43238, 674624, 11030083]);
+        assert_perft(POSITION_4, &[6, 264, 9467, 422333, 15833292]);
+        assert_perft(POSITION_5, &[44, 1486, 62379, 2103487]);
+        assert_perft(POSITION_6, &[46, 2079, 89890, 3894594]);
     }
-    return count;
 }
@@ -1 +1,5 @@
-pub mod generator;
\ No newline at end of file
+pub mod evaluation;
+#[allow(clippy::module_inception)]
+pub mod generator;
+pub mod perft;
+pub mod search;
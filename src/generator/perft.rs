@@ -0,0 +1,252 @@
+use std::{fmt, fs, time::Instant};
+
+use crate::model::{actions::ActionKey, piece::Color, position::Position};
+
+use super::generator;
+
+/**
+ * Line of a divide that does not match the reference divide
+ */
+#[derive(Debug, PartialEq)]
+pub enum Divergence {
+    Missing {
+        notation: String,
+        expected: u64,
+    },
+    Unexpected {
+        notation: String,
+        count: u64,
+    },
+    Count {
+        notation: String,
+        count: u64,
+        expected: u64,
+    },
+}
+
+impl Divergence {
+    pub fn notation(&self) -> &str {
+        match self {
+            Divergence::Missing { notation, .. }
+            | Divergence::Unexpected { notation, .. }
+            | Divergence::Count { notation, .. } => notation,
+        }
+    }
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Divergence::Missing { notation, expected } => {
+                write!(f, "{} : not generated, expected {}", notation, expected)
+            }
+            Divergence::Unexpected { notation, count } => {
+                write!(f, "{} : {}, not in the reference", notation, count)
+            }
+            Divergence::Count {
+                notation,
+                count,
+                expected,
+            } => write!(f, "{} : {}, expected {}", notation, count, expected),
+        }
+    }
+}
+
+/**
+ * Reads a reference divide, one "e2e4: 20" or "e2e4 20" line per move.
+ * Other lines, such as the total printed by most engines, are skipped
+ */
+pub fn parse_reference(text: &str) -> Vec<(String, u64)> {
+    let mut reference: Vec<(String, u64)> = text
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line
+                .split(|c: char| c == ':' || c.is_whitespace())
+                .filter(|part| !part.is_empty());
+            match (parts.next(), parts.next(), parts.next()) {
+                (Some(notation), Some(count), None)
+                    if ActionKey::from_long_algebraic(notation, &Color::WHITE).is_some() =>
+                {
+                    Some((notation.to_string(), count.parse().ok()?))
+                }
+                _ => None,
+            }
+        })
+        .collect();
+    reference.sort();
+    reference
+}
+
+/**
+ * Differences between two divides sorted by notation, in notation order
+ */
+pub fn compare(divide: &[(String, u64)], reference: &[(String, u64)]) -> Vec<Divergence> {
+    let mut divergences = Vec::new();
+    let (mut generated, mut expected) = (divide.iter().peekable(), reference.iter().peekable());
+    loop {
+        match (generated.peek(), expected.peek()) {
+            (Some((notation, count)), Some((reference_notation, reference_count)))
+                if notation == reference_notation =>
+            {
+                if count != reference_count {
+                    divergences.push(Divergence::Count {
+                        notation: notation.clone(),
+                        count: *count,
+                        expected: *reference_count,
+                    });
+                }
+                generated.next();
+                expected.next();
+            }
+            (Some((notation, count)), Some((reference_notation, _)))
+                if notation < reference_notation =>
+            {
+                divergences.push(Divergence::Unexpected {
+                    notation: notation.clone(),
+                    count: *count,
+                });
+                generated.next();
+            }
+            (Some((notation, count)), None) => {
+                divergences.push(Divergence::Unexpected {
+                    notation: notation.clone(),
+                    count: *count,
+                });
+                generated.next();
+            }
+            (_, Some((notation, expected_count))) => {
+                divergences.push(Divergence::Missing {
+                    notation: notation.clone(),
+                    expected: *expected_count,
+                });
+                expected.next();
+            }
+            (None, None) => break,
+        }
+    }
+    divergences
+}
+
+/**
+ * Prints the divide of the board after the moves of `path`. With a reference divide file,
+ * prints the differences and the first diverging move sequence to look into
+ */
+pub fn run<P: Position>(
+    fen: &str,
+    depth: u32,
+    path: &[&str],
+    reference: Option<&str>,
+) -> Result<(), String> {
+    if depth == 0 {
+        return Err("The depth of a divide must be at least 1".to_string());
+    }
+    let mut position = P::from_fen(fen).map_err(|e| format!("Invalid board {}", e.err))?;
+    for notation in path {
+        let action = position
+            .find_action(notation)
+            .ok_or(format!("Illegal move {} in the path", notation))?;
+        position.play(action);
+    }
+    if !path.is_empty() {
+        println!("fen: {}", position.to_fen());
+    }
+
+    let now = Instant::now();
+    let divide = generator::divide(&mut position, depth);
+    for (notation, count) in divide.iter() {
+        println!("{} : {}", notation, count);
+    }
+    println!();
+    println!(
+        "nodes: {}",
+        divide.iter().map(|(_, count)| count).sum::<u64>()
+    );
+    println!("elapsed: {}", now.elapsed().as_millis());
+
+    let Some(file) = reference else {
+        return Ok(());
+    };
+    let text = fs::read_to_string(file).map_err(|e| format!("Cannot read {} : {}", file, e))?;
+    let divergences = compare(&divide, &parse_reference(&text));
+    let Some(first) = divergences.first() else {
+        println!("The divide matches the reference");
+        return Ok(());
+    };
+    println!("{} differences with the reference :", divergences.len());
+    for divergence in divergences.iter() {
+        println!("  {}", divergence);
+    }
+    let mut sequence = path.to_vec();
+    sequence.push(first.notation());
+    println!("First diverging sequence : {}", sequence.join(" "));
+    if matches!(first, Divergence::Count { .. }) && depth > 1 {
+        println!(
+            "Compare the divide of --moves {} at depth {} to go further",
+            sequence.join(" "),
+            depth - 1
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compare, parse_reference, Divergence};
+
+    fn divide(lines: &[(&str, u64)]) -> Vec<(String, u64)> {
+        lines
+            .iter()
+            .map(|(notation, count)| (notation.to_string(), *count))
+            .collect()
+    }
+
+    #[test]
+    fn reads_reference_divides() {
+        let text = "a2a3: 380\ne7e8q 12\n\nb1c3 :  440\nNodes searched: 820\nperft 2\ne2e4 x\n";
+        assert_eq!(
+            parse_reference(text),
+            divide(&[("a2a3", 380), ("b1c3", 440), ("e7e8q", 12)])
+        );
+        assert!(parse_reference("").is_empty());
+    }
+
+    #[test]
+    fn compares_divides() {
+        let reference = divide(&[("a2a3", 380), ("b1c3", 440), ("e2e4", 600)]);
+        assert!(compare(&reference, &reference).is_empty());
+
+        let generated = divide(&[("a2a3", 380), ("a2a4", 420), ("b1c3", 439)]);
+        assert_eq!(
+            compare(&generated, &reference),
+            vec![
+                Divergence::Unexpected {
+                    notation: "a2a4".to_string(),
+                    count: 420
+                },
+                Divergence::Count {
+                    notation: "b1c3".to_string(),
+                    count: 439,
+                    expected: 440
+                },
+                Divergence::Missing {
+                    notation: "e2e4".to_string(),
+                    expected: 600
+                },
+            ]
+        );
+        assert_eq!(
+            compare(&[], &divide(&[("a2a3", 1)])),
+            vec![Divergence::Missing {
+                notation: "a2a3".to_string(),
+                expected: 1
+            }]
+        );
+        assert_eq!(
+            compare(&divide(&[("h2h3", 1)]), &[]),
+            vec![Divergence::Unexpected {
+                notation: "h2h3".to_string(),
+                count: 1
+            }]
+        );
+    }
+}
@@ -0,0 +1,310 @@
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
+};
+
+use crate::model::{
+    actions::{self, Action, ActionKey},
+    board::{Board, Square},
+};
+
+use super::evaluation;
+
+pub const MATE_SCORE: i32 = 100_000;
+pub const MAX_DEPTH: u32 = 64;
+const INFINITY: i32 = MATE_SCORE + 1;
+const NODES_BETWEEN_CHECKS: u64 = 2048;
+
+/**
+ * Limits of a search, without any limit the search runs until it is stopped
+ */
+#[derive(Clone, Copy, Default, Debug)]
+pub struct SearchLimits {
+    pub depth: Option<u32>,
+    pub movetime: Option<Duration>,
+}
+
+#[derive(Clone, Debug)]
+pub struct SearchResult {
+    pub best_move: Option<ActionKey>,
+    pub score: i32,
+    pub depth: u32,
+    pub nodes: u64,
+    pub elapsed: Duration,
+    pub pv: Vec<ActionKey>,
+}
+
+impl SearchResult {
+    /**
+     * Moves until mate, negative when the side to move is getting mated
+     */
+    pub fn mate_in(&self) -> Option<i32> {
+        if self.score.abs() < MATE_SCORE - MAX_DEPTH as i32 * 2 {
+            return None;
+        }
+        let plies = MATE_SCORE - self.score.abs();
+        Some(self.score.signum() * (plies + 1) / 2)
+    }
+}
+
+struct Searcher<'a> {
+    limits: SearchLimits,
+    stop: &'a AtomicBool,
+    start: Instant,
+    nodes: u64,
+    aborted: bool,
+    can_abort: bool,
+    previous_pv: Vec<ActionKey>,
+}
+
+impl Searcher<'_> {
+    fn should_stop(&mut self) -> bool {
+        if self.aborted || !self.can_abort {
+            return self.aborted;
+        }
+        if self.nodes.is_multiple_of(NODES_BETWEEN_CHECKS) {
+            let out_of_time = self
+                .limits
+                .movetime
+                .is_some_and(|movetime| self.start.elapsed() >= movetime);
+            self.aborted = out_of_time || self.stop.load(Ordering::Relaxed);
+        }
+        self.aborted
+    }
+
+    /**
+     * Principal variation move first, then captures from the most valuable victim
+     * by the least valuable attacker, then quiet moves
+     */
+    fn order(&self, board: &Board, moves: &mut [Action], ply: usize) {
+        let pv_move = self.previous_pv.get(ply);
+        moves.sort_by_cached_key(|action| {
+            let key = ActionKey::of(action);
+            if Some(&key) == pv_move {
+                return i32::MIN;
+            }
+            let victim = match board.piece_at_mailbox_index(key.target) {
+                Square::Inside(Some(piece)) => evaluation::piece_value(piece),
+                _ => 0,
+            };
+            let attacker = match board.piece_at_mailbox_index(key.start) {
+                Square::Inside(Some(piece)) => evaluation::piece_value(piece),
+                _ => 0,
+            };
+            let promotion = key
+                .promotion
+                .map_or(0, |piece| evaluation::piece_value(&piece));
+            if victim > 0 || promotion > 0 {
+                -(10 * (victim + promotion) - attacker / 100)
+            } else {
+                0
+            }
+        });
+    }
+
+    fn negamax(
+        &mut self,
+        board: &mut Board,
+        depth: u32,
+        ply: usize,
+        mut alpha: i32,
+        beta: i32,
+        pv: &mut Vec<ActionKey>,
+    ) -> i32 {
+        pv.clear();
+        if ply > 0
+            && (board.halfmove_clock() >= 100
+                || board.repetitions() > 1
+                || board.has_insufficient_material())
+        {
+            return 0;
+        }
+
+        let mut moves = actions::generate_moves(board);
+        if moves.is_empty() {
+            return if actions::is_in_check(board) {
+                -MATE_SCORE + ply as i32
+            } else {
+                0
+            };
+        }
+        if depth == 0 {
+            return self.quiescence(board, alpha, beta);
+        }
+
+        self.order(board, &mut moves, ply);
+        let mut child_pv = Vec::new();
+        for action in moves.into_iter() {
+            let key = ActionKey::of(&action);
+            if board.do_move(action).is_err() {
+                continue;
+            }
+            self.nodes += 1;
+            let score = -self.negamax(board, depth - 1, ply + 1, -beta, -alpha, &mut child_pv);
+            board.undo_last_move();
+            if self.should_stop() {
+                return 0;
+            }
+
+            if score > alpha {
+                alpha = score;
+                pv.clear();
+                pv.push(key);
+                pv.append(&mut child_pv);
+                if alpha >= beta {
+                    break;
+                }
+            }
+        }
+        alpha
+    }
+
+    /**
+     * Only captures and promotions are searched until the position is quiet
+     */
+    fn quiescence(&mut self, board: &mut Board, mut alpha: i32, beta: i32) -> i32 {
+        let stand_pat = evaluation::evaluate(board);
+        if stand_pat >= beta {
+            return beta;
+        }
+        alpha = alpha.max(stand_pat);
+
+        let mut moves = actions::generate_moves(board);
+        moves.retain(|action| {
+            action.promotion().is_some()
+                || matches!(
+                    board.piece_at_mailbox_index(action.target_square()),
+                    Square::Inside(Some(_))
+                )
+        });
+        self.order(board, &mut moves, usize::MAX);
+        for action in moves.into_iter() {
+            if board.do_move(action).is_err() {
+                continue;
+            }
+            self.nodes += 1;
+            let score = -self.quiescence(board, -beta, -alpha);
+            board.undo_last_move();
+            if self.should_stop() {
+                return 0;
+            }
+            if score >= beta {
+                return beta;
+            }
+            alpha = alpha.max(score);
+        }
+        alpha
+    }
+}
+
+/**
+ * Negamax alpha-beta with iterative deepening. `on_iteration` is called after each completed depth,
+ * the search stops at the limits or as soon as `stop` is set, keeping the last completed depth
+ */
+pub fn search<F: FnMut(&SearchResult)>(
+    board: &mut Board,
+    limits: SearchLimits,
+    stop: &AtomicBool,
+    mut on_iteration: F,
+) -> SearchResult {
+    let mut searcher = Searcher {
+        limits,
+        stop,
+        start: Instant::now(),
+        nodes: 0,
+        aborted: false,
+        can_abort: false,
+        previous_pv: Vec::new(),
+    };
+    let mut result = SearchResult {
+        best_move: None,
+        score: 0,
+        depth: 0,
+        nodes: 0,
+        elapsed: Duration::ZERO,
+        pv: Vec::new(),
+    };
+
+    let max_depth = limits.depth.unwrap_or(MAX_DEPTH).clamp(1, MAX_DEPTH);
+    for depth in 1..=max_depth {
+        // The first depth is always completed, so that there is always a move to play
+        searcher.can_abort = depth > 1;
+        let mut pv = Vec::new();
+        let score = searcher.negamax(board, depth, 0, -INFINITY, INFINITY, &mut pv);
+        if searcher.aborted {
+            break;
+        }
+        result = SearchResult {
+            best_move: pv.first().copied(),
+            score,
+            depth,
+            nodes: searcher.nodes,
+            elapsed: searcher.start.elapsed(),
+            pv: pv.clone(),
+        };
+        on_iteration(&result);
+        searcher.previous_pv = pv;
+        if result.mate_in().is_some() || result.best_move.is_none() {
+            break;
+        }
+    }
+    result.nodes = searcher.nodes;
+    result.elapsed = searcher.start.elapsed();
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use super::{search, SearchLimits};
+    use crate::model::{board::START_FEN, fixtures::board};
+
+    const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+    fn depth(depth: u32) -> SearchLimits {
+        SearchLimits {
+            depth: Some(depth),
+            movetime: None,
+        }
+    }
+
+    #[test]
+    fn finds_a_mate_in_one() {
+        let mut board = board("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1");
+        let result = search(&mut board, depth(4), &AtomicBool::new(false), |_| ());
+        assert_eq!(result.best_move.unwrap().to_string(), "a1a8");
+        assert_eq!(result.mate_in(), Some(1));
+        assert_eq!(result.depth, 1);
+    }
+
+    #[test]
+    fn stops_at_the_depth_limit() {
+        let mut board = board(START_FEN);
+        let mut depths = Vec::new();
+        let result = search(&mut board, depth(3), &AtomicBool::new(false), |result| {
+            depths.push(result.depth)
+        });
+        assert_eq!(depths, [1, 2, 3]);
+        assert_eq!(result.depth, 3);
+        assert!(result.best_move.is_some());
+        assert_eq!(board.to_fen(), START_FEN);
+    }
+
+    #[test]
+    fn keeps_the_last_completed_depth_when_stopped() {
+        let mut board = board(KIWIPETE);
+        let stop = AtomicBool::new(false);
+        let mut completed = None;
+        let result = search(&mut board, SearchLimits::default(), &stop, |result| {
+            if result.depth == 2 {
+                completed = result.best_move;
+                stop.store(true, Ordering::Relaxed);
+            }
+        });
+        assert_eq!(result.depth, 2);
+        assert!(completed.is_some());
+        assert_eq!(result.best_move, completed);
+        assert_eq!(board.to_fen(), KIWIPETE);
+    }
+}
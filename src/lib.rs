@@ -0,0 +1,42 @@
+/*!
+ * Chess rules, move generation and engine, usable without the Tetra window.
+ *
+ * - [`model::board::Board`] holds a position : FEN import and export, playing and undoing actions
+ * - [`model::actions`] generates the legal actions of a board
+ * - [`model::algebraic_notation`] reads and writes Standard Algebraic Notation
+ * - [`model::status`] tells whether the game is over
+ * - [`model::pgn`] imports and exports complete games
+ * - [`generator`] counts nodes (perft), evaluates and searches positions
+ * - [`protocol`] speaks UCI and XBoard
+ *
+ * Headless consumers depend on the crate with `default-features = false`, which leaves out the
+ * `gui` feature and its Tetra and SDL2 dependencies, and the terminal views of the `cli` feature.
+ *
+ * ```
+ * use rust_tutorial::model::{
+ *     actions::{self, ActionKey},
+ *     algebraic_notation,
+ *     board::{Board, START_FEN},
+ * };
+ *
+ * let mut board = Board::from_fen(START_FEN.to_string()).unwrap();
+ * assert_eq!(actions::generate_moves(&board).len(), 20);
+ *
+ * let e4 = ActionKey::from_long_algebraic("e2e4", board.color_turn()).unwrap();
+ * board.try_play(&e4).unwrap();
+ * let e5 = algebraic_notation::from_algebraic_notation(&board, "e5").unwrap();
+ * board.do_move(e5).unwrap();
+ *
+ * assert_eq!(
+ *     board.to_fen(),
+ *     "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2"
+ * );
+ * assert!(!board.status().is_over());
+ * ```
+ */
+
+pub mod generator;
+pub mod model;
+pub mod protocol;
+#[cfg(feature = "cli")]
+pub mod view;
@@ -1,64 +1,245 @@
-use std::{env, time::Instant};
+use std::{
+    env,
+    time::{Duration, Instant},
+};
+#[cfg(feature = "gui")]
 use tetra::ContextBuilder;
-use view::tetra_state::{TetraState, SQUARE_SIZE};
 
-use crate::model::board::Board;
-mod generator;
-mod model;
-mod view;
+use rust_tutorial::{
+    generator::{self, search::SearchLimits},
+    model::{
+        bitboard::BitboardPosition,
+        board::{Board, START_FEN},
+        piece::Color,
+        position::Position,
+    },
+    protocol, view,
+};
+#[cfg(feature = "gui")]
+use rust_tutorial::{
+    model::clock::{Delay, TimeControl},
+    view::tetra_state::{TetraState, PANEL_WIDTH, SQUARE_SIZE},
+};
 
-const FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR";
-const TURN: u32 = 1;
+const USAGE: &str =
+    "Usage : rust_tutorial [--bench <depth> | --perft <depth> ... | --console ... | --uci | --xboard]\n\
+     Without a mode, opens the game window when built with the gui feature :\n\
+     rust_tutorial [--engine <white|black>] [--depth <depth>] [--movetime <milliseconds>] [--flip]\n\
+                   [--clock <minutes>[+<increment seconds>] [--delay <seconds> | --bronstein <seconds>]]";
+const CONSOLE_USAGE: &str =
+    "Usage : --console [--engine <white|black>] [--depth <depth>] [--movetime <milliseconds>]";
 
 fn main() {
-    let has_bench = env::args()
-        .skip(1)
-        .collect::<Vec<String>>()
-        .chunks(2)
-        .map(|window| (window[0].clone(), window[1].clone()))
-        .find(|(key, val)| key == "--bench");
-    if let Some((_, value)) = has_bench {
-        bench(value.parse::<u32>().unwrap());
-    } else {
-        run();
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("--bench") => {
+            let depth = args.get(1).and_then(|depth| depth.parse::<u32>().ok());
+            match (depth, args.get(2).map(String::as_str)) {
+                (Some(depth), None) => bench::<Board>(depth),
+                (Some(depth), Some("--bitboard")) => bench::<BitboardPosition>(depth),
+                _ => eprintln!("Usage : --bench <depth> [--bitboard]"),
+            }
+        }
+        Some("--perft") => {
+            if let Err(error) = perft(&args[1..]) {
+                eprintln!("{}", error);
+            }
+        }
+        Some("--console") => match engine_options(&args[1..], CONSOLE_USAGE) {
+            Ok((engine, limits)) => view::repl::run(engine, limits),
+            Err(error) => eprintln!("{}", error),
+        },
+        Some("--uci") => protocol::uci::run(),
+        Some("--xboard") => protocol::xboard::run(),
+        #[cfg(feature = "gui")]
+        None
+        | Some(
+            "--engine" | "--depth" | "--movetime" | "--flip" | "--clock" | "--delay"
+            | "--bronstein",
+        ) => match window_options(&args) {
+            Ok(options) => {
+                if let Err(error) = run(options) {
+                    eprintln!("{}", error);
+                }
+            }
+            Err(error) => eprintln!("{}", error),
+        },
+        _ => eprintln!("{}", USAGE),
     }
 }
 
-fn bench(depth : u32) {
+/**
+ * Perft of the start position, with the mailbox board or the bitboard backend
+ */
+fn bench<P: Position>(depth: u32) {
     let now = Instant::now();
-    let mut board = {
-        let this = Board::from_fen(FEN.to_string());
-        match this {
-            Ok(t) => t,
-            Err(e) => panic!("Invalid board {}", e.err),
-        }
+    let mut position = match P::from_fen(START_FEN) {
+        Ok(position) => position,
+        Err(e) => panic!("Invalid board {}", e.err),
     };
-    board.turn = TURN;
     println!(
         "count: {}",
-        generator::generator::count_actions(&mut board, depth, true)
+        generator::generator::count_actions(&mut position, depth, true)
     );
     println!("elapsed: {}", now.elapsed().as_millis());
 }
 
-fn run() -> tetra::Result {
-    let mut board = {
-        let this = Board::from_fen(FEN.to_string());
+/**
+ * --perft <depth> [--fen <fen>] [--moves <move> ...] [--reference <file>] [--bitboard]
+ */
+fn perft(args: &[String]) -> Result<(), String> {
+    let usage = "Usage : --perft <depth> [--fen <fen>] [--moves <move> ...] [--reference <file>] \
+                 [--bitboard]";
+    let depth = args
+        .first()
+        .and_then(|depth| depth.parse::<u32>().ok())
+        .ok_or(usage)?;
+    let mut fen: Vec<&str> = Vec::new();
+    let mut path: Vec<&str> = Vec::new();
+    let mut reference = None;
+    let mut bitboard = false;
+    let mut option = None;
+    for arg in args[1..].iter().map(String::as_str) {
+        match (arg, option) {
+            ("--bitboard", _) => {
+                bitboard = true;
+                option = None;
+            }
+            ("--fen" | "--moves" | "--reference", _) => option = Some(arg),
+            // The FEN may be given as a single argument or as its six fields
+            (_, Some("--fen")) => fen.push(arg),
+            (_, Some("--moves")) => path.push(arg),
+            (_, Some("--reference")) => reference = Some(arg),
+            _ => return Err(usage.to_string()),
+        }
+    }
+    let fen = if fen.is_empty() {
+        START_FEN.to_string()
+    } else {
+        fen.join(" ")
+    };
+    if bitboard {
+        generator::perft::run::<BitboardPosition>(&fen, depth, &path, reference)
+    } else {
+        generator::perft::run::<Board>(&fen, depth, &path, reference)
+    }
+}
+
+/**
+ * [--engine <white|black>] [--depth <depth>] [--movetime <milliseconds>], the engine thinks for
+ * a second when no limit is given
+ */
+fn engine_options(args: &[String], usage: &str) -> Result<(Option<Color>, SearchLimits), String> {
+    let mut engine = None;
+    let mut limits = SearchLimits::default();
+    let mut args = args.iter().map(String::as_str);
+    while let Some(option) = args.next() {
+        match (option, args.next()) {
+            ("--engine", Some("white")) => engine = Some(Color::WHITE),
+            ("--engine", Some("black")) => engine = Some(Color::BLACK),
+            ("--depth", Some(depth)) => {
+                limits.depth = Some(depth.parse().map_err(|_| usage.to_string())?)
+            }
+            ("--movetime", Some(milliseconds)) => {
+                let milliseconds = milliseconds.parse().map_err(|_| usage.to_string())?;
+                limits.movetime = Some(Duration::from_millis(milliseconds))
+            }
+            _ => return Err(usage.to_string()),
+        }
+    }
+    if limits.depth.is_none() && limits.movetime.is_none() {
+        limits.movetime = Some(Duration::from_secs(1));
+    }
+    Ok((engine, limits))
+}
+
+#[cfg(feature = "gui")]
+struct WindowOptions {
+    engine: Option<Color>,
+    limits: SearchLimits,
+    flipped: bool,
+    time_control: Option<TimeControl>,
+}
+
+/**
+ * The engine options, plus [--flip] [--clock <minutes>[+<increment seconds>]]
+ * [--delay <seconds> | --bronstein <seconds>]
+ */
+#[cfg(feature = "gui")]
+fn window_options(args: &[String]) -> Result<WindowOptions, String> {
+    let mut flip = false;
+    let mut time_control: Option<TimeControl> = None;
+    let mut delay = Delay::None;
+    let mut engine_args = Vec::new();
+    let mut args = args.iter();
+    while let Some(option) = args.next() {
+        match option.as_str() {
+            "--flip" => flip = true,
+            "--clock" => {
+                let notation = args.next().ok_or(USAGE)?;
+                time_control = Some(notation.parse().map_err(|error| format!("{}", error))?);
+            }
+            "--delay" | "--bronstein" => {
+                let seconds = args
+                    .next()
+                    .and_then(|seconds| seconds.parse::<f64>().ok())
+                    .filter(|seconds| seconds.is_finite() && *seconds >= 0.0)
+                    .map(Duration::from_secs_f64)
+                    .ok_or(USAGE)?;
+                delay = if option == "--delay" {
+                    Delay::Simple(seconds)
+                } else {
+                    Delay::Bronstein(seconds)
+                };
+            }
+            _ => engine_args.push(option.clone()),
+        }
+    }
+    let time_control = match (time_control, delay) {
+        (Some(time_control), delay) => Some(TimeControl {
+            delay,
+            ..time_control
+        }),
+        (None, Delay::None) => None,
+        (None, _) => return Err("A delay needs a --clock".to_string()),
+    };
+    let (engine, limits) = engine_options(&engine_args, USAGE)?;
+    Ok(WindowOptions {
+        engine,
+        limits,
+        // The side of the player is at the bottom, unless turned around
+        flipped: flip != (engine == Some(Color::WHITE)),
+        time_control,
+    })
+}
+
+#[cfg(feature = "gui")]
+fn run(options: WindowOptions) -> tetra::Result {
+    let board = {
+        let this = Board::from_fen(START_FEN.to_string());
         match this {
             Ok(t) => t,
             Err(e) => panic!("Invalid board {}", e.err),
         }
     };
-    board.turn = TURN;
 
     let mut context = ContextBuilder::new(
         "Hello, world!",
-        8 * SQUARE_SIZE as i32,
+        (8.0 * SQUARE_SIZE + PANEL_WIDTH) as i32,
         8 * SQUARE_SIZE as i32,
     )
     .quit_on_escape(true)
     .show_mouse(true)
     .build()?;
 
-    context.run(|ctx| TetraState::new(ctx, board))
+    context.run(|ctx| {
+        TetraState::new(
+            ctx,
+            board,
+            options.engine,
+            options.limits,
+            options.flipped,
+            options.time_control,
+        )
+    })
 }
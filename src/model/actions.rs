@@ -1,31 +1,69 @@
-use crate::model::board::{Square::*};
+use crate::model::board::Square::*;
 use std::collections::HashMap;
+use std::fmt;
 use std::ops::{Deref, DerefMut};
 
-use super::board::{Board, InvalidMoveError, Square};
-use super::chess_actions::capture::Capture;
-use super::chess_actions::castle::Castle;
-use super::chess_actions::movement::Move;
+use super::algebraic_notation;
+use super::board::{Board, CastleRights, InvalidMoveError, Square, BOARD_X, TO_MAILBOX};
+use super::chess_actions::{capture, castle, movement, promote};
 use super::piece::{self, Color, Piece};
+use super::position::Position;
+
+/**
+ * Most legal moves a chess position can have is 218
+ */
+pub const MAX_MOVES: usize = 256;
 
 /**
  * Command pattern :
  * Move
- * Castle
+ * DoublePush
  * Capture
- * Promotion
+ * EnPassant
+ * Castle
+ * Any of them but castles may also promote
+ */
+#[derive(PartialEq, Clone, Copy, Debug, Default)]
+pub enum ActionKind {
+    #[default]
+    Move,
+    DoublePush,
+    Capture,
+    EnPassant,
+    Castle,
+}
+
+/**
+ * A move between two mailbox squares. It holds no state, the captured piece is kept by the board
+ * so that actions can be copied around freely
+ */
+#[derive(PartialEq, Clone, Copy, Debug, Default)]
+pub struct Action {
+    start: u8,
+    target: u8,
+    pub kind: ActionKind,
+    promotion: Option<Piece>,
+}
+
+/**
+ * Legal actions of a position, stored inline. Holds the actions of the mailbox Board unless told
+ * otherwise
  */
-pub trait ChessAction: std::fmt::Debug {
-    fn execute(&mut self, board: &mut Board) -> Result<(), InvalidMoveError>;
-    fn undo(&mut self, board: &mut Board) -> Result<(), InvalidMoveError>;
-    fn as_promotion(&self, color: &Color) -> Result<MovesList, String>;
-    fn to_algebraic_notation(&self, board: &Board) -> String;
-    fn target_square(&self) -> usize;
-    fn start_square(&self) -> usize;
-    fn double_forward(&self) -> Option<(usize, usize)>;
+#[derive(Clone)]
+pub struct MovesList<A = Action> {
+    actions: [A; MAX_MOVES],
+    len: usize,
 }
 
-pub struct MovesList(pub Vec<Box<dyn ChessAction>>);
+/**
+ * Identifies an action by its squares and promotion, to remember it without owning it
+ */
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct ActionKey {
+    pub start: usize,
+    pub target: usize,
+    pub promotion: Option<Piece>,
+}
 
 pub struct BoardPins(pub HashMap<usize, PinState>);
 
@@ -33,38 +71,265 @@ pub enum PinState {
     Pinned(i32),
     Locked,
 }
-pub struct BoardAttackData {
-    pub white_king: usize,
-    pub black_king: usize,
-    pins: BoardPins,
-    resolve_check: Vec<usize>,
+
+impl Action {
+    pub fn new(start: usize, target: usize, kind: ActionKind) -> Self {
+        Action {
+            start: start as u8,
+            target: target as u8,
+            kind,
+            promotion: None,
+        }
+    }
+
+    pub fn promoting_to(self, piece: Piece) -> Self {
+        Action {
+            promotion: Some(piece),
+            ..self
+        }
+    }
+
+    /**
+     * Plays the action, giving back the captured piece
+     */
+    pub fn execute(&self, board: &mut Board) -> Result<Option<Piece>, InvalidMoveError> {
+        let (start, target) = (self.start_square(), self.target_square());
+        let captured = match self.kind {
+            ActionKind::Move | ActionKind::DoublePush => {
+                movement::execute(board, start, target)?;
+                None
+            }
+            ActionKind::Capture => Some(capture::execute(board, start, target, target)?),
+            ActionKind::EnPassant => Some(capture::execute(
+                board,
+                start,
+                target,
+                self.en_passant_square(),
+            )?),
+            ActionKind::Castle => {
+                castle::execute(board, start, target)?;
+                None
+            }
+        };
+        if let Some(piece) = self.promotion {
+            promote::execute(board, start, target, piece)?;
+        }
+        Ok(captured)
+    }
+
+    /**
+     * Takes the action back, the captured piece being the one given by `execute`
+     */
+    pub fn undo(&self, board: &mut Board, captured: Option<Piece>) -> Result<(), InvalidMoveError> {
+        let (start, target) = (self.start_square(), self.target_square());
+        if let Some(piece) = self.promotion {
+            promote::undo(board, start, target, piece.get_color())?;
+        }
+        match (self.kind, captured) {
+            (ActionKind::Castle, _) => castle::undo(board, start, target),
+            (ActionKind::Capture, Some(piece)) => {
+                capture::undo(board, start, target, target, piece)
+            }
+            (ActionKind::EnPassant, Some(piece)) => {
+                capture::undo(board, start, target, self.en_passant_square(), piece)
+            }
+            (ActionKind::Capture | ActionKind::EnPassant, None) => Err(InvalidMoveError {
+                start,
+                end: target,
+                reason: "no captured piece to put back".to_string(),
+            }),
+            _ => movement::undo(board, start, target),
+        }
+    }
+
+    pub fn promotion(&self) -> Option<Piece> {
+        self.promotion
+    }
+
+    pub fn to_algebraic_notation(self, board: &mut Board) -> String {
+        algebraic_notation::to_algebraic_notation(board, &self)
+    }
+
+    pub fn target_square(&self) -> usize {
+        self.target as usize
+    }
+
+    pub fn start_square(&self) -> usize {
+        self.start as usize
+    }
+
+    /**
+     * (ghost, pawn) squares of a pawn moving two squares, that may be taken en passant
+     */
+    pub fn double_forward(&self) -> Option<(usize, usize)> {
+        match self.kind {
+            ActionKind::DoublePush => Some((
+                (self.start_square() + self.target_square()) / 2,
+                self.target_square(),
+            )),
+            _ => None,
+        }
+    }
+
+    /**
+     * Square of the pawn taken en passant : on the starting row, in the target column
+     */
+    fn en_passant_square(&self) -> usize {
+        self.start_square() - self.start_square() % BOARD_X + self.target_square() % BOARD_X
+    }
+}
+
+impl<A: Copy + Default> MovesList<A> {
+    pub fn new() -> Self {
+        MovesList {
+            actions: [A::default(); MAX_MOVES],
+            len: 0,
+        }
+    }
+
+    pub fn push(&mut self, action: A) {
+        self.actions[self.len] = action;
+        self.len += 1;
+    }
+
+    pub fn pop(&mut self) -> Option<A> {
+        self.len = self.len.checked_sub(1)?;
+        Some(self.actions[self.len])
+    }
+
+    /**
+     * Keeps the actions matching the predicate, in the same order
+     */
+    pub fn retain<F: FnMut(&A) -> bool>(&mut self, mut keep: F) {
+        let mut kept = 0;
+        for index in 0..self.len {
+            if keep(&self.actions[index]) {
+                self.actions[kept] = self.actions[index];
+                kept += 1;
+            }
+        }
+        self.len = kept;
+    }
 }
 
 impl MovesList {
-    pub fn to_algebraic_notation(&self, board: &Board) -> String {
+    pub fn to_algebraic_notation(&self, board: &mut Board) -> String {
         let mut result = String::from("");
-        for (index, current) in self.0.iter().enumerate() {
-            result += format!(
-                "{0} : {1}\n",
-                index.to_string(),
-                current.to_algebraic_notation(board)
-            )
-            .as_str()
+        for (index, current) in self.iter().enumerate() {
+            result += format!("{0} : {1}\n", index, current.to_algebraic_notation(board)).as_str()
         }
-        String::from(result)
+        result
+    }
+
+    /**
+     * Removes the action identified by the key from the list
+     */
+    pub fn take(&mut self, key: &ActionKey) -> Option<Action> {
+        let index = self
+            .iter()
+            .position(|action| ActionKey::of(action) == *key)?;
+        let action = self.actions[index];
+        self.actions.copy_within(index + 1..self.len, index);
+        self.len -= 1;
+        Some(action)
     }
 }
 
-impl Deref for MovesList {
-    type Target = Vec<Box<dyn ChessAction>>;
-    fn deref(&self) -> &Vec<Box<dyn ChessAction>> {
-        &self.0
+impl<A: Copy + Default> Default for MovesList<A> {
+    fn default() -> Self {
+        MovesList::new()
+    }
+}
+
+impl<A: Copy + Default> Extend<A> for MovesList<A> {
+    fn extend<T: IntoIterator<Item = A>>(&mut self, actions: T) {
+        for action in actions {
+            self.push(action);
+        }
     }
 }
 
-impl DerefMut for MovesList {
+impl<A> IntoIterator for MovesList<A> {
+    type Item = A;
+    type IntoIter = std::iter::Take<std::array::IntoIter<A, MAX_MOVES>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.actions.into_iter().take(self.len)
+    }
+}
+
+impl<'a, A> IntoIterator for &'a MovesList<A> {
+    type Item = &'a A;
+    type IntoIter = std::slice::Iter<'a, A>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl ActionKey {
+    pub fn of(action: &Action) -> Self {
+        ActionKey {
+            start: action.start_square(),
+            target: action.target_square(),
+            promotion: action.promotion(),
+        }
+    }
+
+    /**
+     * Reads long algebraic notation such as e2e4 or e7e8q, the promoted piece gets the given color
+     */
+    pub fn from_long_algebraic(notation: &str, color: &Color) -> Option<Self> {
+        if !notation.is_ascii() || !(4..=5).contains(&notation.len()) {
+            return None;
+        }
+        let start = Board::square_from_name(&notation[0..2])?;
+        let target = Board::square_from_name(&notation[2..4])?;
+        let color = *color;
+        let promotion = match notation[4..].chars().next() {
+            None => None,
+            Some('q') => Some(Piece::Queen { color }),
+            Some('r') => Some(Piece::Rook { color }),
+            Some('b') => Some(Piece::Bishop { color }),
+            Some('n') => Some(Piece::Knight { color }),
+            Some(_) => return None,
+        };
+        Some(ActionKey {
+            start,
+            target,
+            promotion,
+        })
+    }
+}
+
+/**
+ * Long algebraic notation, as used by UCI : e2e4, e7e8q
+ */
+impl fmt::Display for ActionKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}{}",
+            Board::square_name(self.start),
+            Board::square_name(self.target)
+        )?;
+        if let Some(piece) = self.promotion {
+            write!(f, "{}", piece.to_fen_char().to_ascii_lowercase())?;
+        }
+        Ok(())
+    }
+}
+
+impl<A> Deref for MovesList<A> {
+    type Target = [A];
+    fn deref(&self) -> &[A] {
+        &self.actions[..self.len]
+    }
+}
+
+impl<A> DerefMut for MovesList<A> {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+        &mut self.actions[..self.len]
     }
 }
 
@@ -86,7 +351,7 @@ impl BoardPins {
         if let Some(state) = self.get(&pos) {
             match state {
                 PinState::Pinned(dir) => direction == *dir || -direction == *dir,
-                PinState::Locked => return false,
+                PinState::Locked => false,
             }
         } else {
             true
@@ -111,37 +376,28 @@ pub fn can_king_move(
 
     for direction in piece::DIRECTIONS {
         match board.ray(position, direction) {
-            Some((
-                hit,
-                Piece::King {
-                    color,
-                    first_move: _,
-                },
-            )) if color == king_color => {
+            Some((hit, Piece::King { color })) if color == king_color => {
                 if let Some((second_hit, piece)) = board.ray(hit, direction) {
-                    if color == king_color {
-                        if piece.get_color() != king_color
-                            && ((piece.is_sliding() && piece.has_direction(-direction))
-                                || piece
-                                    .get_attack_direction()
-                                    .contains(&(position as i32 - second_hit as i32)))
-                        {
-                            return false;
-                        }
+                    if piece.get_color() != king_color
+                        && ((piece.is_sliding() && piece.has_direction(-direction))
+                            || piece
+                                .get_attack_direction()
+                                .contains(&(position as i32 - second_hit as i32)))
+                    {
+                        return false;
                     }
                 }
             }
-            Some((hit, piece)) => {
+            Some((hit, piece))
                 if piece.get_color() != king_color
                     && ((piece.is_sliding() && piece.has_direction(-direction))
                         || piece
                             .get_attack_direction()
-                            .contains(&(position as i32 - hit as i32)))
-                {
-                    return false;
-                }
+                            .contains(&(position as i32 - hit as i32))) =>
+            {
+                return false;
             }
-            None => (),
+            _ => (),
         };
     }
 
@@ -159,10 +415,28 @@ pub fn can_king_move(
     true
 }
 
-pub fn generate_moves(board: &Board) -> MovesList {
-    let mut moves = MovesList(Vec::new());
+/**
+ * Whether the king of the color to play is attacked
+ */
+pub fn is_in_check(board: &Board) -> bool {
+    let color = board.color_turn();
+    !can_king_move(board, color, board.get_king_by_color(color), 0)
+}
+
+/**
+ * Legal actions of any position, the mailbox Board and the BitboardPosition alike
+ */
+pub fn generate_moves<P: Position>(position: &P) -> P::Actions {
+    position.legal_actions()
+}
+
+/**
+ * Legal actions of the mailbox Board, found from the pins and checks around its king
+ */
+pub(crate) fn generate_board_moves(board: &Board) -> MovesList {
+    let mut moves = MovesList::new();
     let playing_color = board.color_turn();
-    let king_position = board.get_king_by_color(&playing_color);
+    let king_position = board.get_king_by_color(playing_color);
     let mut pins: BoardPins = BoardPins(HashMap::new());
     let mut resolve_check: Vec<usize> = vec![];
 
@@ -177,11 +451,9 @@ pub fn generate_moves(board: &Board) -> MovesList {
                         && behind.is_sliding()
                         && behind.has_direction(-direction)
                     {
-                        if pins.contains_key(&position) {
-                            pins.insert(position, PinState::Locked);
-                        } else {
-                            pins.insert(position, PinState::Pinned(direction));
-                        }
+                        pins.entry(position)
+                            .and_modify(|state| *state = PinState::Locked)
+                            .or_insert(PinState::Pinned(direction));
                     }
                 }
             } else {
@@ -210,11 +482,11 @@ pub fn generate_moves(board: &Board) -> MovesList {
         let knight_position = (king_position as i32 + direction) as usize;
         if let Inside(Some(Piece::Knight {
             color: knight_color,
-        })) = board.piece_at_mailbox_index(knight_position as usize)
+        })) = board.piece_at_mailbox_index(knight_position)
         {
             if knight_color != playing_color {
                 if resolve_check.is_empty() {
-                    resolve_check.push(knight_position as usize);
+                    resolve_check.push(knight_position);
                 } else {
                     double_check = true;
                     break;
@@ -225,14 +497,11 @@ pub fn generate_moves(board: &Board) -> MovesList {
 
     // King must move
     if double_check {
-        if let Inside(Some(
-            piece @ Piece::King {
-                color: _,
-                first_move: _,
-            },
-        )) = board.piece_at_mailbox_index(king_position)
+        if let Inside(Some(piece @ Piece::King { .. })) =
+            board.piece_at_mailbox_index(king_position)
         {
-            return piece.valid_moves(king_position, board, &resolve_check, &pins);
+            piece.valid_moves(&mut moves, king_position, board, &resolve_check, &pins);
+            return moves;
         } else {
             panic!("invalid king position")
         }
@@ -242,25 +511,24 @@ pub fn generate_moves(board: &Board) -> MovesList {
         if let Some(PinState::Locked) = pins.get(&index) {
             continue;
         }
-        moves.append(&mut piece.valid_moves(index, board, &resolve_check, &pins))
+        piece.valid_moves(&mut moves, index, board, &resolve_check, &pins)
     }
 
     moves
 }
 pub fn get_moves_for_piece_and_direction(
+    moves: &mut MovesList,
     start: usize,
     direction: i32,
-    is_slide: bool,
     current_piece: &Piece,
     board: &Board,
-    resolve_check: &Vec<usize>,
+    resolve_check: &[usize],
     pins: &BoardPins,
-) -> MovesList {
-    let mut moves = MovesList(Vec::new());
-
+) {
     if !pins.can_move_in_direction(start, direction) {
-        return moves;
+        return;
     }
+    let is_slide = current_piece.is_sliding();
 
     let mut end = (start as i32 + direction) as usize;
     loop {
@@ -280,12 +548,11 @@ pub fn get_moves_for_piece_and_direction(
                 match option {
                     Some(piece) => {
                         if piece.get_color() != current_piece.get_color() {
-                            let capture = Capture::new(Move::new(start, end), None, None);
-                            moves.push(Box::new(capture));
+                            moves.push(Action::new(start, end, ActionKind::Capture));
                         }
                         break;
                     }
-                    None => moves.push(Box::new(Move::new(start, end))),
+                    None => moves.push(Action::new(start, end, ActionKind::Move)),
                 }
             }
         };
@@ -296,151 +563,114 @@ pub fn get_moves_for_piece_and_direction(
 
         end = (end as i32 + direction) as usize;
     }
-    moves
 }
 
-pub fn pawn_captures(
-    from: usize,
-    to: usize,
-    color: &Color,
-    board: &Board,
-) -> Option<Box<dyn ChessAction>> {
+pub fn pawn_captures(from: usize, to: usize, color: &Color, board: &Board) -> Option<Action> {
     if let Square::Inside(Some(piece)) = board.piece_at_mailbox_index(to) {
         if piece.get_color() != color {
-            return Some(Box::new(Capture::new(Move::new(from, to), None, None)));
+            return Some(Action::new(from, to, ActionKind::Capture));
         }
-    } else if let Some((ghost, pawn)) = board.double_pawn_move {
+    } else if let Some((ghost, pawn)) = board.double_pawn_move() {
         if ghost == to {
+            // Both pawns leave the rank at once, which may expose the king to a rook or a queen
             let dir: i32 = pawn as i32 - from as i32;
-            match board.ray(from, dir) {
-                Some((
-                    _,
-                    Piece::King {
-                        color,
-                        first_move: _,
-                    },
-                )) => {
-                    if let Some((_, piece)) = board.ray(pawn, -dir) {
-                        if board.color_turn() == color
-                            && piece.get_color() != color
-                            && piece.is_sliding()
-                            && piece.has_direction(dir)
-                        {
-                            return None;
-                        }
-                    }
-                }
-                Some((_, piece)) => {
-                    if piece.is_sliding()
-                        && piece.has_direction(-dir)
-                        && board.color_turn() != piece.get_color()
-                    {
-                        if let Some((
-                            _,
-                            Piece::King {
-                                color,
-                                first_move: _,
-                            },
-                        )) = board.ray(pawn, -dir)
-                        {
-                            if piece.get_color() == color
-                                && piece.is_sliding()
-                                && piece.has_direction(dir)
-                            {
-                                return None;
-                            }
-                        }
-                    }
+            let exposes_king = match (board.ray(from, -dir), board.ray(pawn, dir)) {
+                (Some((_, Piece::King { color: king })), Some((_, attacker)))
+                | (Some((_, attacker)), Some((_, Piece::King { color: king }))) => {
+                    king == color
+                        && attacker.get_color() != color
+                        && attacker.is_sliding()
+                        && attacker.has_direction(dir)
                 }
-                _ => (),
+                _ => false,
             };
+            if exposes_king {
+                return None;
+            }
 
-            return Some(Box::new(Capture::new(
-                Move::new(from, to),
-                None,
-                Some(pawn),
-            )));
+            return Some(Action::new(from, to, ActionKind::EnPassant));
         }
     }
     None
 }
 
 /**
- * Trangression : we should check that the rook and king are on the last row
+ * Castles allowed by the castling rights of the king's color
  */
-pub fn castles(king_position: usize, piece: &Piece, board: &Board) -> MovesList {
-    if let Piece::King {
-        color: _,
-        first_move,
-    } = piece
+pub fn castles(moves: &mut MovesList, king_position: usize, piece: &Piece, board: &Board) {
+    let color = piece.get_color();
+    let rights = board.castle_rights(color);
+    let initial_position = match color {
+        Color::WHITE => TO_MAILBOX[60],
+        Color::BLACK => TO_MAILBOX[4],
+    };
+
+    if king_position != initial_position
+        || rights == CastleRights::None
+        || !can_king_move(board, color, king_position, 0)
     {
-        if *first_move != u32::MAX || !can_king_move(board, piece.get_color(), king_position, 0) {
-            return MovesList(Vec::new());
-        }
-        let mut moves = MovesList(Vec::new());
-        if let Some((
-            pos,
-            Piece::Rook {
-                color: _,
-                first_move,
-            },
-        )) = board.ray(king_position, -1)
-        {
-            if *first_move == u32::MAX
-                && can_king_move(board, piece.get_color(), king_position, -1)
-                && can_king_move(board, piece.get_color(), king_position, -2)
-            {
-                moves.push(Box::new(Castle::new(
-                    Move::new(king_position, king_position - 2),
-                    Move::new(pos, king_position - 1),
-                )))
-            }
-        }
-        if let Some((
-            pos,
-            Piece::Rook {
-                color: _,
-                first_move,
-            },
-        )) = board.ray(king_position, 1)
-        {
-            if *first_move == u32::MAX
-                && can_king_move(board, piece.get_color(), king_position, 1)
-                && can_king_move(board, piece.get_color(), king_position, 2)
-            {
-                moves.push(Box::new(Castle::new(
-                    Move::new(king_position, king_position + 2),
-                    Move::new(pos, king_position + 1),
-                )))
-            }
-        }
+        return;
+    }
+    if rights.has_king_side() {
+        moves.extend(castle(board, color, king_position, king_position + 3, 1));
+    }
+    if rights.has_queen_side() {
+        moves.extend(castle(board, color, king_position, king_position - 4, -1));
+    }
+}
 
-        return moves;
-    } else {
-        return MovesList(Vec::new());
+/**
+ * The rook must still be in its corner with only empty squares up to the king,
+ * and the king must not cross or land on an attacked square
+ */
+fn castle(
+    board: &Board,
+    color: &Color,
+    king_position: usize,
+    rook_position: usize,
+    direction: i32,
+) -> Option<Action> {
+    match board.ray(king_position, direction) {
+        Some((position, Piece::Rook { color: rook_color }))
+            if position == rook_position && rook_color == color => {}
+        _ => return None,
+    }
+    if !can_king_move(board, color, king_position, direction)
+        || !can_king_move(board, color, king_position, 2 * direction)
+    {
+        return None;
     }
+    Some(Action::new(
+        king_position,
+        (king_position as i32 + 2 * direction) as usize,
+        ActionKind::Castle,
+    ))
 }
 
-fn to_promotion(
-    move_option: Option<Box<dyn ChessAction>>,
+/**
+ * Expands a pawn action reaching the last row into one promotion per piece
+ */
+pub fn to_promotion(
+    moves: &mut MovesList,
+    action: Option<Action>,
     current_piece: &Piece,
     end: usize,
-) -> MovesList {
-    move_option
-        .map(|retrieved_move| {
-            if *current_piece
-                == (Piece::Pawn {
-                    color: *current_piece.get_color(),
-                })
-                && Board::is_on_promote_flag(current_piece.get_color(), end)
-            {
-                match retrieved_move.as_promotion(current_piece.get_color()) {
-                    Ok(promotions) => promotions,
-                    Err(_) => MovesList(vec![retrieved_move]),
-                }
-            } else {
-                MovesList(vec![retrieved_move])
-            }
-        })
-        .unwrap_or(MovesList(Vec::new()))
+) {
+    let Some(action) = action else {
+        return;
+    };
+    let color = *current_piece.get_color();
+    if *current_piece == (Piece::Pawn { color }) && Board::is_on_promote_flag(&color, end) {
+        moves.extend(
+            [
+                Piece::Bishop { color },
+                Piece::Knight { color },
+                Piece::Rook { color },
+                Piece::Queen { color },
+            ]
+            .map(|piece| action.promoting_to(piece)),
+        );
+    } else {
+        moves.push(action);
+    }
 }
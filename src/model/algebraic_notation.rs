@@ -1,6 +1,382 @@
-use super::{board::Board, actions::ChessAction, chess_actions::movement::Move};
+use std::fmt;
 
-pub fn from_algebraic_notation(board : &Board, notation : String) -> impl ChessAction {
+use super::{
+    actions::{self, Action},
+    board::{Board, Square},
+};
 
-    Move{start: 0, end : 0}
+#[derive(Debug, PartialEq)]
+pub enum AlgebraicNotationError {
+    Malformed(String),
+    Illegal(String),
+    Ambiguous(String),
+}
+
+impl fmt::Display for AlgebraicNotationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AlgebraicNotationError::Malformed(notation) => {
+                write!(f, "'{}' is not valid algebraic notation", notation)
+            }
+            AlgebraicNotationError::Illegal(notation) => {
+                write!(f, "'{}' is not a legal move", notation)
+            }
+            AlgebraicNotationError::Ambiguous(notation) => {
+                write!(f, "'{}' matches several legal moves", notation)
+            }
+        }
+    }
+}
+
+/**
+ * A SAN move once parsed, before being resolved against the legal moves of a board
+ */
+enum Notation {
+    Castle {
+        king_side: bool,
+    },
+    Standard {
+        piece: char,
+        from_file: Option<char>,
+        from_rank: Option<char>,
+        capture: bool,
+        target: usize,
+        promotion: Option<char>,
+    },
+}
+
+fn parse(notation: &str) -> Option<Notation> {
+    let notation = notation.trim_end_matches(['+', '#', '!', '?']);
+    match notation {
+        "O-O" | "0-0" => return Some(Notation::Castle { king_side: true }),
+        "O-O-O" | "0-0-0" => return Some(Notation::Castle { king_side: false }),
+        _ => (),
+    }
+
+    let mut chars: Vec<char> = notation.chars().collect();
+
+    let promotion = match chars.as_slice() {
+        [.., '=', promotion @ ('Q' | 'R' | 'B' | 'N')] => {
+            let promotion = *promotion;
+            chars.truncate(chars.len() - 2);
+            Some(promotion)
+        }
+        [.., '1'..='8', promotion @ ('Q' | 'R' | 'B' | 'N')] => {
+            let promotion = *promotion;
+            chars.truncate(chars.len() - 1);
+            Some(promotion)
+        }
+        _ => None,
+    };
+
+    let piece = match chars.first() {
+        Some(piece @ ('N' | 'B' | 'R' | 'Q' | 'K')) => {
+            let piece = *piece;
+            chars.remove(0);
+            piece
+        }
+        _ => 'P',
+    };
+
+    if chars.len() < 2 {
+        return None;
+    }
+    let target = Board::square_from_name(&String::from_iter(&chars[chars.len() - 2..]))?;
+    chars.truncate(chars.len() - 2);
+
+    let capture = chars.last() == Some(&'x');
+    if capture {
+        chars.pop();
+    }
+
+    let (from_file, from_rank) = match chars.as_slice() {
+        [] => (None, None),
+        [file @ 'a'..='h'] => (Some(*file), None),
+        [rank @ '1'..='8'] => (None, Some(*rank)),
+        [file @ 'a'..='h', rank @ '1'..='8'] => (Some(*file), Some(*rank)),
+        _ => return None,
+    };
+
+    if promotion.is_some() && piece != 'P' {
+        return None;
+    }
+
+    Some(Notation::Standard {
+        piece,
+        from_file,
+        from_rank,
+        capture,
+        target,
+        promotion,
+    })
+}
+
+fn piece_letter(board: &Board, position: usize) -> Option<char> {
+    match board.piece_at_mailbox_index(position) {
+        Square::Inside(Some(piece)) => Some(piece.to_fen_char().to_ascii_uppercase()),
+        _ => None,
+    }
+}
+
+/**
+ * A pawn capture is the only move changing file without landing on a piece : en passant
+ */
+fn is_capture(board: &Board, action: &Action) -> bool {
+    matches!(
+        board.piece_at_mailbox_index(action.target_square()),
+        Square::Inside(Some(_))
+    ) || (piece_letter(board, action.start_square()) == Some('P')
+        && Board::get_file(action.start_square()) != Board::get_file(action.target_square()))
+}
+
+impl Notation {
+    fn matches(&self, board: &Board, action: &Action) -> bool {
+        let start = action.start_square();
+        let target = action.target_square();
+        match self {
+            Notation::Castle { king_side } => {
+                piece_letter(board, start) == Some('K')
+                    && start.abs_diff(target) == 2
+                    && (target > start) == *king_side
+            }
+            Notation::Standard {
+                piece,
+                from_file,
+                from_rank,
+                target: notation_target,
+                promotion,
+                ..
+            } => {
+                *notation_target == target
+                    && piece_letter(board, start) == Some(*piece)
+                    && from_file.is_none_or(|file| file == Board::get_file(start))
+                    // A pawn only changes file when capturing, which names its starting file
+                    && (*piece != 'P'
+                        || from_file.is_some()
+                        || Board::get_file(start) == Board::get_file(target))
+                    && from_rank.is_none_or(|rank| rank == Board::get_column(start))
+                    && action
+                        .promotion()
+                        .map(|promoted| promoted.to_fen_char().to_ascii_uppercase())
+                        == *promotion
+                    && !(piece_letter(board, start) == Some('K') && start.abs_diff(target) == 2)
+            }
+        }
+    }
+}
+
+/**
+ * Resolves a move in Standard Algebraic Notation (Nbd2, exd6, O-O-O, e8=Q+, Rxa1#...)
+ * against the legal moves of the board.
+ * The 'x' of a capture may be left out, "ed5" reads as "exd5", but an 'x' on a move which
+ * captures nothing is illegal
+ */
+pub fn from_algebraic_notation(
+    board: &Board,
+    notation: &str,
+) -> Result<Action, AlgebraicNotationError> {
+    let notation = notation.trim();
+    let parsed = parse(notation).ok_or(AlgebraicNotationError::Malformed(notation.to_string()))?;
+
+    let mut candidates: Vec<Action> = actions::generate_moves(board)
+        .into_iter()
+        .filter(|action| parsed.matches(board, action))
+        .collect();
+
+    match candidates.len() {
+        0 => Err(AlgebraicNotationError::Illegal(notation.to_string())),
+        1 => {
+            let action = candidates.pop().unwrap();
+            if let Notation::Standard { capture: true, .. } = parsed {
+                if !is_capture(board, &action) {
+                    return Err(AlgebraicNotationError::Illegal(notation.to_string()));
+                }
+            }
+            Ok(action)
+        }
+        _ => Err(AlgebraicNotationError::Ambiguous(notation.to_string())),
+    }
+}
+
+/**
+ * Suffix telling whether the action checks ('+') or mates ('#') the opponent
+ */
+fn check_suffix(board: &mut Board, action: &Action) -> &'static str {
+    let played = actions::generate_moves(board)
+        .into_iter()
+        .find(|legal| legal == action);
+    let played = match played {
+        Some(played) => played,
+        None => return "",
+    };
+
+    if board.do_move(played).is_err() {
+        return "";
+    }
+    let suffix = if !actions::is_in_check(board) {
+        ""
+    } else if actions::generate_moves(board).is_empty() {
+        "#"
+    } else {
+        "+"
+    };
+    board.undo_last_move();
+    suffix
+}
+
+/**
+ * Standard Algebraic Notation of a legal action, computed on the board before it is played
+ */
+pub fn to_algebraic_notation(board: &mut Board, action: &Action) -> String {
+    let start = action.start_square();
+    let target = action.target_square();
+    let piece = piece_letter(board, start)
+        .expect("Should not have happened : A move was created without a valid piece");
+
+    let mut notation = String::new();
+    if piece == 'K' && start.abs_diff(target) == 2 {
+        notation.push_str(if target > start { "O-O" } else { "O-O-O" });
+    } else {
+        let capture = is_capture(board, action);
+        if piece == 'P' {
+            if capture {
+                notation.push(Board::get_file(start));
+            }
+        } else {
+            notation.push(piece);
+
+            // Other pieces of the same kind able to reach the same square
+            let others: Vec<usize> = actions::generate_moves(board)
+                .iter()
+                .filter(|other| {
+                    other.target_square() == target
+                        && other.start_square() != start
+                        && piece_letter(board, other.start_square()) == Some(piece)
+                })
+                .map(|other| other.start_square())
+                .collect();
+            if !others.is_empty() {
+                if others
+                    .iter()
+                    .all(|other| Board::get_file(*other) != Board::get_file(start))
+                {
+                    notation.push(Board::get_file(start));
+                } else if others
+                    .iter()
+                    .all(|other| Board::get_column(*other) != Board::get_column(start))
+                {
+                    notation.push(Board::get_column(start));
+                } else {
+                    notation.push_str(&Board::square_name(start));
+                }
+            }
+        }
+        if capture {
+            notation.push('x');
+        }
+        notation.push_str(&Board::square_name(target));
+        if let Some(promoted) = action.promotion() {
+            notation.push('=');
+            notation.push(promoted.to_fen_char().to_ascii_uppercase());
+        }
+    }
+
+    notation.push_str(check_suffix(board, action));
+    notation
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{from_algebraic_notation, to_algebraic_notation, AlgebraicNotationError};
+    use crate::model::{
+        board::{Board, START_FEN},
+        fixtures::board,
+    };
+
+    /**
+     * Reads the SAN and writes it back from the action found
+     */
+    fn assert_round_trip(fen: &str, san: &str) {
+        let mut board = board(fen);
+        let action = from_algebraic_notation(&board, san).unwrap();
+        assert_eq!(to_algebraic_notation(&mut board, &action), san, "{}", fen);
+    }
+
+    #[test]
+    fn disambiguates_by_file_rank_or_square() {
+        assert_round_trip("4k3/8/8/8/8/8/8/1N3N1K w - - 0 1", "Nbd2");
+        assert_round_trip("4k3/8/8/8/8/8/8/1N3N1K w - - 0 1", "Nfd2");
+        assert_round_trip("4k3/8/8/R7/8/8/8/R6K w - - 0 1", "R1a3");
+        assert_round_trip("4k3/8/8/R7/8/8/8/R6K w - - 0 1", "R5a3");
+        assert_round_trip("4k3/8/8/8/8/Q7/8/Q1Q4K w - - 0 1", "Qa1b2");
+        assert_eq!(
+            from_algebraic_notation(&board("4k3/8/8/8/8/8/8/1N3N1K w - - 0 1"), "Nd2"),
+            Err(AlgebraicNotationError::Ambiguous("Nd2".to_string()))
+        );
+    }
+
+    #[test]
+    fn reads_promotions_checks_and_mates() {
+        assert_round_trip("4k3/P7/8/8/8/8/8/4K3 w - - 0 1", "a8=Q+");
+        assert_round_trip("4k3/P7/8/8/8/8/8/4K3 w - - 0 1", "a8=N");
+        assert_round_trip("1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1", "axb8=R+");
+        assert_round_trip("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", "Ra8#");
+        // The suffixes are optional when reading
+        let mate = board("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1");
+        assert_eq!(
+            from_algebraic_notation(&mate, "Ra8"),
+            from_algebraic_notation(&mate, "Ra8#")
+        );
+        assert!(from_algebraic_notation(&board("4k3/P7/8/8/8/8/8/4K3 w - - 0 1"), "a8").is_err());
+    }
+
+    #[test]
+    fn reads_castling() {
+        let fen = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+        assert_round_trip(fen, "O-O");
+        assert_round_trip(fen, "O-O-O");
+        assert_round_trip("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1", "O-O-O");
+        let board = board(fen);
+        assert_eq!(
+            from_algebraic_notation(&board, "0-0"),
+            from_algebraic_notation(&board, "O-O")
+        );
+        assert!(from_algebraic_notation(&board, "Kg1").is_err());
+    }
+
+    #[test]
+    fn reads_en_passant() {
+        let fen = "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1";
+        assert_round_trip(fen, "exd6");
+        assert_eq!(
+            from_algebraic_notation(&board(fen), "d6"),
+            Err(AlgebraicNotationError::Illegal("d6".to_string()))
+        );
+        let board = board(fen);
+        let action = from_algebraic_notation(&board, "exd6").unwrap();
+        assert_eq!(Board::square_name(action.target_square()), "d6");
+    }
+
+    #[test]
+    fn accepts_captures_without_x() {
+        let fen = "4k3/8/8/3p4/4P3/8/8/4K3 w - - 0 1";
+        let pawns = board(fen);
+        assert_eq!(
+            from_algebraic_notation(&pawns, "ed5"),
+            from_algebraic_notation(&pawns, "exd5")
+        );
+        assert_round_trip(fen, "exd5");
+        assert_eq!(
+            from_algebraic_notation(&pawns, "d5"),
+            Err(AlgebraicNotationError::Illegal("d5".to_string()))
+        );
+        assert_eq!(
+            from_algebraic_notation(&pawns, "exe5"),
+            Err(AlgebraicNotationError::Illegal("exe5".to_string()))
+        );
+        assert_eq!(
+            from_algebraic_notation(&board(START_FEN), "Nxf3"),
+            Err(AlgebraicNotationError::Illegal("Nxf3".to_string()))
+        );
+    }
 }
@@ -0,0 +1,598 @@
+use std::{iter, sync::OnceLock};
+
+use super::{
+    actions::{ActionKey, MovesList},
+    board::{Board, CastleRights, InvalidBoardErr, TO_BOARD, TO_MAILBOX},
+    piece::{Color, Piece},
+    position::Position,
+};
+
+/*
+ * Squares go from 0 (a8) to 63 (h1) like the board indexes of Board,
+ * bit n of a bitboard being set when square n is occupied
+ */
+
+const PAWN: usize = 0;
+const KNIGHT: usize = 1;
+const BISHOP: usize = 2;
+const ROOK: usize = 3;
+const QUEEN: usize = 4;
+const KING: usize = 5;
+
+/**
+ * (rank, file) steps, ranks going down from rank 8 to rank 1
+ */
+const DIRECTIONS: [(i32, i32); 8] = [
+    (-1, 0),
+    (1, 0),
+    (0, 1),
+    (0, -1),
+    (-1, 1),
+    (-1, -1),
+    (1, 1),
+    (1, -1),
+];
+const ROOK_DIRECTIONS: [usize; 4] = [0, 1, 2, 3];
+const BISHOP_DIRECTIONS: [usize; 4] = [4, 5, 6, 7];
+const KNIGHT_STEPS: [(i32, i32); 8] = [
+    (-2, -1),
+    (-2, 1),
+    (-1, -2),
+    (-1, 2),
+    (1, -2),
+    (1, 2),
+    (2, -1),
+    (2, 1),
+];
+
+struct AttackTables {
+    knight: [u64; 64],
+    king: [u64; 64],
+    pawn: [[u64; 64]; 2], // Squares attacked by a white or a black pawn
+    rays: [[u64; 64]; 8], // Squares from a square to the edge, in each direction
+}
+
+fn bit(square: usize) -> u64 {
+    1 << square
+}
+
+fn offset(square: usize, (rank_step, file_step): (i32, i32)) -> Option<usize> {
+    let rank = (square / 8) as i32 + rank_step;
+    let file = (square % 8) as i32 + file_step;
+    ((0..8).contains(&rank) && (0..8).contains(&file)).then(|| (rank * 8 + file) as usize)
+}
+
+fn tables() -> &'static AttackTables {
+    static TABLES: OnceLock<AttackTables> = OnceLock::new();
+    TABLES.get_or_init(|| {
+        let mut tables = AttackTables {
+            knight: [0; 64],
+            king: [0; 64],
+            pawn: [[0; 64]; 2],
+            rays: [[0; 64]; 8],
+        };
+        for square in 0..64 {
+            for step in KNIGHT_STEPS {
+                tables.knight[square] |= offset(square, step).map_or(0, bit);
+            }
+            for (direction, step) in DIRECTIONS.iter().enumerate() {
+                tables.king[square] |= offset(square, *step).map_or(0, bit);
+                let mut current = square;
+                while let Some(next) = offset(current, *step) {
+                    tables.rays[direction][square] |= bit(next);
+                    current = next;
+                }
+            }
+            for file_step in [-1, 1] {
+                tables.pawn[0][square] |= offset(square, (-1, file_step)).map_or(0, bit);
+                tables.pawn[1][square] |= offset(square, (1, file_step)).map_or(0, bit);
+            }
+        }
+        tables
+    })
+}
+
+/**
+ * Classical ray attacks : the ray is cut after its first blocker
+ */
+fn ray_attacks(direction: usize, square: usize, occupied: u64) -> u64 {
+    let ray = tables().rays[direction][square];
+    let blockers = ray & occupied;
+    if blockers == 0 {
+        return ray;
+    }
+    let (rank_step, file_step) = DIRECTIONS[direction];
+    // The closest blocker is the lowest square when the ray goes towards higher squares
+    let blocker = if rank_step * 8 + file_step > 0 {
+        blockers.trailing_zeros()
+    } else {
+        63 - blockers.leading_zeros()
+    } as usize;
+    ray ^ tables().rays[direction][blocker]
+}
+
+fn sliding_attacks(directions: &[usize], square: usize, occupied: u64) -> u64 {
+    directions.iter().fold(0, |attacks, direction| {
+        attacks | ray_attacks(*direction, square, occupied)
+    })
+}
+
+fn squares(mut bitboard: u64) -> impl Iterator<Item = usize> {
+    iter::from_fn(move || {
+        (bitboard != 0).then(|| {
+            let square = bitboard.trailing_zeros() as usize;
+            bitboard &= bitboard - 1;
+            square
+        })
+    })
+}
+
+fn color_index(color: &Color) -> usize {
+    match color {
+        Color::WHITE => 0,
+        Color::BLACK => 1,
+    }
+}
+
+fn kind_index(piece: &Piece) -> usize {
+    match piece {
+        Piece::Pawn { .. } => PAWN,
+        Piece::Knight { .. } => KNIGHT,
+        Piece::Bishop { .. } => BISHOP,
+        Piece::Rook { .. } => ROOK,
+        Piece::Queen { .. } => QUEEN,
+        Piece::King { .. } => KING,
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+enum MoveKind {
+    #[default]
+    Normal,
+    DoublePush,
+    EnPassant,
+    Castle,
+}
+
+/**
+ * Action of a BitboardPosition, castles are king moves of two squares
+ */
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct BitMove {
+    pub from: usize,
+    pub to: usize,
+    pub promotion: Option<Piece>,
+    kind: MoveKind,
+}
+
+/**
+ * State restored when undoing a move
+ */
+struct Undo {
+    action: BitMove,
+    captured: Option<Piece>,
+    castle_rights: [CastleRights; 2],
+    en_passant: Option<usize>,
+    halfmove_clock: u32,
+}
+
+pub struct BitboardPosition {
+    pieces: [u64; 6],
+    colors: [u64; 2],
+    squares: [Option<Piece>; 64],
+    side: Color,
+    castle_rights: [CastleRights; 2],
+    en_passant: Option<usize>, // Square behind a pawn that just moved two squares
+    halfmove_clock: u32,
+    turn: u32, // Half moves since the start of the game, starting at 1
+    history: Vec<Undo>,
+}
+
+impl BitboardPosition {
+    fn empty() -> Self {
+        BitboardPosition {
+            pieces: [0; 6],
+            colors: [0; 2],
+            squares: [None; 64],
+            side: Color::WHITE,
+            castle_rights: [CastleRights::None; 2],
+            en_passant: None,
+            halfmove_clock: 0,
+            turn: 1,
+            history: Vec::new(),
+        }
+    }
+
+    fn put(&mut self, square: usize, piece: Piece) {
+        self.pieces[kind_index(&piece)] |= bit(square);
+        self.colors[color_index(piece.get_color())] |= bit(square);
+        self.squares[square] = Some(piece);
+    }
+
+    fn take(&mut self, square: usize) -> Option<Piece> {
+        let piece = self.squares[square].take()?;
+        self.pieces[kind_index(&piece)] &= !bit(square);
+        self.colors[color_index(piece.get_color())] &= !bit(square);
+        Some(piece)
+    }
+
+    fn occupied(&self) -> u64 {
+        self.colors[0] | self.colors[1]
+    }
+
+    fn king_square(&self, color: usize) -> usize {
+        (self.pieces[KING] & self.colors[color]).trailing_zeros() as usize
+    }
+
+    /**
+     * Whether one of the `attackers` pieces of the `by` color attacks the square,
+     * with the given occupancy so that moves can be tested without being played
+     */
+    fn is_attacked(&self, square: usize, by: usize, occupied: u64, attackers: u64) -> bool {
+        let tables = tables();
+        let diagonal = (self.pieces[BISHOP] | self.pieces[QUEEN]) & attackers;
+        let straight = (self.pieces[ROOK] | self.pieces[QUEEN]) & attackers;
+        // A pawn of `by` attacks the square when a pawn of the other color would attack it back
+        tables.pawn[1 - by][square] & self.pieces[PAWN] & attackers != 0
+            || tables.knight[square] & self.pieces[KNIGHT] & attackers != 0
+            || tables.king[square] & self.pieces[KING] & attackers != 0
+            || diagonal != 0
+                && sliding_attacks(&BISHOP_DIRECTIONS, square, occupied) & diagonal != 0
+            || straight != 0 && sliding_attacks(&ROOK_DIRECTIONS, square, occupied) & straight != 0
+    }
+
+    /**
+     * Square of the pawn taken by an en passant capture landing on `to`
+     */
+    fn en_passant_victim(&self, to: usize) -> usize {
+        match self.side {
+            Color::WHITE => to + 8,
+            Color::BLACK => to - 8,
+        }
+    }
+
+    fn push_pawn_moves(&self, moves: &mut MovesList<BitMove>, from: usize, to: usize) {
+        let last_rank = match self.side {
+            Color::WHITE => 0,
+            Color::BLACK => 7,
+        };
+        if to / 8 != last_rank {
+            moves.push(BitMove {
+                from,
+                to,
+                promotion: None,
+                kind: MoveKind::Normal,
+            });
+            return;
+        }
+        let color = self.side;
+        for promotion in [
+            Piece::Queen { color },
+            Piece::Rook { color },
+            Piece::Bishop { color },
+            Piece::Knight { color },
+        ] {
+            moves.push(BitMove {
+                from,
+                to,
+                promotion: Some(promotion),
+                kind: MoveKind::Normal,
+            });
+        }
+    }
+
+    fn pseudo_legal_moves(&self) -> MovesList<BitMove> {
+        let tables = tables();
+        let us = color_index(&self.side);
+        let own = self.colors[us];
+        let enemies = self.colors[1 - us];
+        let occupied = own | enemies;
+        let mut moves = MovesList::new();
+        let mut push = |from: usize, targets: u64, kind: MoveKind| {
+            for to in squares(targets) {
+                moves.push(BitMove {
+                    from,
+                    to,
+                    promotion: None,
+                    kind,
+                });
+            }
+        };
+
+        for from in squares(self.pieces[KNIGHT] & own) {
+            push(from, tables.knight[from] & !own, MoveKind::Normal);
+        }
+        for from in squares((self.pieces[BISHOP] | self.pieces[QUEEN]) & own) {
+            let attacks = sliding_attacks(&BISHOP_DIRECTIONS, from, occupied);
+            push(from, attacks & !own, MoveKind::Normal);
+        }
+        for from in squares((self.pieces[ROOK] | self.pieces[QUEEN]) & own) {
+            let attacks = sliding_attacks(&ROOK_DIRECTIONS, from, occupied);
+            push(from, attacks & !own, MoveKind::Normal);
+        }
+        let king = self.king_square(us);
+        push(king, tables.king[king] & !own, MoveKind::Normal);
+        self.push_castles(&mut push, king, occupied);
+
+        let (forward, start_rank) = match self.side {
+            Color::WHITE => (-1, 6),
+            Color::BLACK => (1, 1),
+        };
+        let mut pawn_moves = MovesList::new();
+        for from in squares(self.pieces[PAWN] & own) {
+            if let Some(one) = offset(from, (forward, 0)).filter(|one| occupied & bit(*one) == 0) {
+                self.push_pawn_moves(&mut pawn_moves, from, one);
+                if let Some(two) = offset(one, (forward, 0)) {
+                    if from / 8 == start_rank && occupied & bit(two) == 0 {
+                        push(from, bit(two), MoveKind::DoublePush);
+                    }
+                }
+            }
+            let attacks = tables.pawn[us][from];
+            for to in squares(attacks & enemies) {
+                self.push_pawn_moves(&mut pawn_moves, from, to);
+            }
+            if let Some(en_passant) = self.en_passant {
+                push(from, attacks & bit(en_passant), MoveKind::EnPassant);
+            }
+        }
+        moves.extend(pawn_moves);
+        moves
+    }
+
+    /**
+     * Castles allowed by the rights, with the rook in its corner, empty squares in between
+     * and a king that neither is in check nor crosses an attacked square
+     */
+    fn push_castles<F: FnMut(usize, u64, MoveKind)>(
+        &self,
+        push: &mut F,
+        king: usize,
+        occupied: u64,
+    ) {
+        let us = color_index(&self.side);
+        let rank = match self.side {
+            Color::WHITE => 56,
+            Color::BLACK => 0,
+        };
+        let rights = self.castle_rights[us];
+        let attackers = self.colors[1 - us];
+        if king != rank + 4 || self.is_attacked(king, 1 - us, occupied, attackers) {
+            return;
+        }
+        let own_rooks = self.pieces[ROOK] & self.colors[us];
+        if rights.has_king_side()
+            && own_rooks & bit(rank + 7) != 0
+            && occupied & (bit(rank + 5) | bit(rank + 6)) == 0
+            && !self.is_attacked(rank + 5, 1 - us, occupied, attackers)
+        {
+            push(king, bit(rank + 6), MoveKind::Castle);
+        }
+        if rights.has_queen_side()
+            && own_rooks & bit(rank) != 0
+            && occupied & (bit(rank + 1) | bit(rank + 2) | bit(rank + 3)) == 0
+            && !self.is_attacked(rank + 3, 1 - us, occupied, attackers)
+        {
+            push(king, bit(rank + 2), MoveKind::Castle);
+        }
+    }
+
+    /**
+     * The king must not be attacked once the move is played
+     */
+    fn is_legal(&self, action: &BitMove) -> bool {
+        let us = color_index(&self.side);
+        let mut occupied = (self.occupied() & !bit(action.from)) | bit(action.to);
+        let mut attackers = self.colors[1 - us] & !bit(action.to);
+        if action.kind == MoveKind::EnPassant {
+            let victim = self.en_passant_victim(action.to);
+            occupied &= !bit(victim);
+            attackers &= !bit(victim);
+        }
+        let king = match self.squares[action.from] {
+            Some(Piece::King { .. }) => action.to,
+            _ => self.king_square(us),
+        };
+        !self.is_attacked(king, 1 - us, occupied, attackers)
+    }
+
+    /**
+     * Any move from or to the initial square of a king or a rook loses the matching castling rights
+     */
+    fn revoke_castle_rights(&mut self, square: usize) {
+        let [white, black] = &mut self.castle_rights;
+        match square {
+            60 => *white = CastleRights::None,
+            63 => *white = white.without_king_side(),
+            56 => *white = white.without_queen_side(),
+            4 => *black = CastleRights::None,
+            7 => *black = black.without_king_side(),
+            0 => *black = black.without_queen_side(),
+            _ => (),
+        }
+    }
+}
+
+impl Position for BitboardPosition {
+    type Action = BitMove;
+    type Actions = MovesList<BitMove>;
+
+    /**
+     * The FEN is validated by Board, then copied square by square
+     */
+    fn from_fen(fen: &str) -> Result<Self, InvalidBoardErr> {
+        let board = Board::from_fen(fen.to_string())?;
+        let mut position = BitboardPosition::empty();
+        for (index, piece) in board.pieces_iter() {
+            position.put(TO_BOARD[index] as usize, *piece);
+        }
+        position.side = *board.color_turn();
+        position.castle_rights = [
+            board.castle_rights(&Color::WHITE),
+            board.castle_rights(&Color::BLACK),
+        ];
+        position.en_passant = board
+            .double_pawn_move()
+            .map(|(ghost, _)| TO_BOARD[ghost] as usize);
+        position.halfmove_clock = board.halfmove_clock();
+        position.turn = board.turn();
+        Ok(position)
+    }
+
+    fn to_fen(&self) -> String {
+        let mut fen = String::new();
+        for rank in 0..8 {
+            let mut empty = 0;
+            for square in rank * 8..rank * 8 + 8 {
+                match self.squares[square] {
+                    Some(piece) => {
+                        if empty > 0 {
+                            fen.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        fen.push(piece.to_fen_char());
+                    }
+                    None => empty += 1,
+                }
+            }
+            if empty > 0 {
+                fen.push_str(&empty.to_string());
+            }
+            if rank < 7 {
+                fen.push('/');
+            }
+        }
+
+        let [white, black] = self.castle_rights;
+        let mut castles = String::new();
+        for (allowed, letter) in [
+            (white.has_king_side(), 'K'),
+            (white.has_queen_side(), 'Q'),
+            (black.has_king_side(), 'k'),
+            (black.has_queen_side(), 'q'),
+        ] {
+            if allowed {
+                castles.push(letter);
+            }
+        }
+        if castles.is_empty() {
+            castles.push('-');
+        }
+
+        let side = match self.side {
+            Color::WHITE => "w",
+            Color::BLACK => "b",
+        };
+        let en_passant = self.en_passant.map_or("-".to_string(), |square| {
+            Board::square_name(TO_MAILBOX[square])
+        });
+        format!(
+            "{} {} {} {} {} {}",
+            fen,
+            side,
+            castles,
+            en_passant,
+            self.halfmove_clock,
+            self.turn.div_ceil(2)
+        )
+    }
+
+    fn side_to_move(&self) -> Color {
+        self.side
+    }
+
+    fn piece_at(&self, square: usize) -> Option<Piece> {
+        self.squares[square]
+    }
+
+    fn legal_actions(&self) -> MovesList<BitMove> {
+        let mut moves = self.pseudo_legal_moves();
+        moves.retain(|action| self.is_legal(action));
+        moves
+    }
+
+    fn action_key(&self, action: &BitMove) -> ActionKey {
+        ActionKey {
+            start: TO_MAILBOX[action.from],
+            target: TO_MAILBOX[action.to],
+            promotion: action.promotion,
+        }
+    }
+
+    fn play(&mut self, action: BitMove) {
+        let undo = Undo {
+            action,
+            captured: None,
+            castle_rights: self.castle_rights,
+            en_passant: self.en_passant,
+            halfmove_clock: self.halfmove_clock,
+        };
+        let captured = match action.kind {
+            MoveKind::EnPassant => self.take(self.en_passant_victim(action.to)),
+            _ => self.take(action.to),
+        };
+        let piece = self.take(action.from).expect("No piece to move");
+        self.put(action.to, action.promotion.unwrap_or(piece));
+        if action.kind == MoveKind::Castle {
+            let (rook_from, rook_to) = if action.to > action.from {
+                (action.from + 3, action.from + 1)
+            } else {
+                (action.from - 4, action.from - 1)
+            };
+            if let Some(rook) = self.take(rook_from) {
+                self.put(rook_to, rook);
+            }
+        }
+
+        self.revoke_castle_rights(action.from);
+        self.revoke_castle_rights(action.to);
+        self.en_passant = match action.kind {
+            MoveKind::DoublePush => Some((action.from + action.to) / 2),
+            _ => None,
+        };
+        self.halfmove_clock = if captured.is_some() || matches!(piece, Piece::Pawn { .. }) {
+            0
+        } else {
+            self.halfmove_clock + 1
+        };
+        self.side = self.side.next();
+        self.turn += 1;
+        self.history.push(Undo { captured, ..undo });
+    }
+
+    fn undo(&mut self) -> Option<BitMove> {
+        let undo = self.history.pop()?;
+        let action = undo.action;
+        self.side = self.side.next();
+        self.turn -= 1;
+
+        let moved = self.take(action.to).expect("No piece to move back");
+        let piece = match action.promotion {
+            Some(_) => Piece::Pawn { color: self.side },
+            None => moved,
+        };
+        self.put(action.from, piece);
+        if action.kind == MoveKind::Castle {
+            let (rook_from, rook_to) = if action.to > action.from {
+                (action.from + 3, action.from + 1)
+            } else {
+                (action.from - 4, action.from - 1)
+            };
+            if let Some(rook) = self.take(rook_to) {
+                self.put(rook_from, rook);
+            }
+        }
+        if let Some(captured) = undo.captured {
+            let square = match action.kind {
+                MoveKind::EnPassant => self.en_passant_victim(action.to),
+                _ => action.to,
+            };
+            self.put(square, captured);
+        }
+
+        self.castle_rights = undo.castle_rights;
+        self.en_passant = undo.en_passant;
+        self.halfmove_clock = undo.halfmove_clock;
+        Some(action)
+    }
+}
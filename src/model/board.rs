@@ -1,9 +1,11 @@
-use std::collections::VecDeque;
+use std::{error::Error, fmt};
 
-use super::actions::ChessAction;
+use super::actions::{self, Action, ActionKey};
 
 use super::piece::Color;
 use super::piece::Piece;
+use super::status::GameStatus;
+use super::zobrist;
 
 pub const BOARD_X: usize = 10;
 pub const BOARD_Y: usize = 12;
@@ -11,6 +13,8 @@ pub const BOARD_SIZE: usize = BOARD_X * BOARD_Y;
 pub const MAX_PIECES_COUNT: usize = 32;
 pub const BLACK_ROW: usize = 2;
 pub const WHITE_ROW: usize = 9;
+pub const HISTORY_CAPACITY: usize = 1024;
+pub const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
 
 pub const TO_MAILBOX: [usize; 64] = [
     21, 22, 23, 24, 25, 26, 27, 28, 31, 32, 33, 34, 35, 36, 37, 38, 41, 42, 43, 44, 45, 46, 47, 48,
@@ -24,46 +28,163 @@ pub const TO_BOARD: [i32; 120] = [
     43, 44, 45, 46, 47, -1, -1, 48, 49, 50, 51, 52, 53, 54, 55, -1, -1, 56, 57, 58, 59, 60, 61, 62,
     63, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1,
 ];
+#[derive(Debug)]
 pub struct InvalidBoardErr {
     pub err: String,
 }
 
+#[derive(PartialEq, Debug)]
 pub struct InvalidMoveError {
     pub start: usize,
     pub end: usize,
     pub reason: String,
 }
 
+#[derive(Debug)]
 pub struct InvalidRemovalError {
     pub position: usize,
     pub reason: String,
 }
 
-#[derive(Debug)]
+/**
+ * Why an action given to `Board::try_play` was refused
+ */
+#[derive(PartialEq, Debug)]
+pub enum PlayError {
+    GameOver(GameStatus),
+    NoPiece { square: usize },
+    WrongSide { square: usize, color: Color },
+    Illegal(ActionKey),
+    Invalid(InvalidMoveError),
+}
+
+impl fmt::Display for InvalidBoardErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid board : {}", self.err)
+    }
+}
+
+impl fmt::Display for InvalidMoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "invalid move {}{} : {}",
+            Board::square_name(self.start),
+            Board::square_name(self.end),
+            self.reason
+        )
+    }
+}
+
+impl fmt::Display for InvalidRemovalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "invalid change on {} : {}",
+            Board::square_name(self.position),
+            self.reason
+        )
+    }
+}
+
+impl fmt::Display for PlayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PlayError::GameOver(status) => write!(f, "the game is over : {}", status),
+            PlayError::NoPiece { square } => {
+                write!(f, "there is no piece on {}", Board::square_name(*square))
+            }
+            PlayError::WrongSide { square, color } => write!(
+                f,
+                "the piece on {} cannot move, {} is to play",
+                Board::square_name(*square),
+                match color {
+                    Color::WHITE => "white",
+                    Color::BLACK => "black",
+                }
+            ),
+            PlayError::Illegal(key) => write!(f, "{} is not a legal move", key),
+            PlayError::Invalid(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl Error for InvalidBoardErr {}
+impl Error for InvalidMoveError {}
+impl Error for InvalidRemovalError {}
+impl Error for PlayError {}
+
+#[derive(Clone, Debug)]
 pub enum Square {
     Inside(Option<Piece>),
     Outside,
 }
 
+#[derive(PartialEq, Clone, Copy, Debug)]
 pub enum CastleRights {
     All,
     KingSide,
     QueenSide,
     None,
 }
+
+impl CastleRights {
+    pub fn from_sides(king_side: bool, queen_side: bool) -> Self {
+        match (king_side, queen_side) {
+            (true, true) => CastleRights::All,
+            (true, false) => CastleRights::KingSide,
+            (false, true) => CastleRights::QueenSide,
+            (false, false) => CastleRights::None,
+        }
+    }
+
+    pub fn has_king_side(&self) -> bool {
+        matches!(self, CastleRights::All | CastleRights::KingSide)
+    }
+
+    pub fn has_queen_side(&self) -> bool {
+        matches!(self, CastleRights::All | CastleRights::QueenSide)
+    }
+
+    pub fn without_king_side(&self) -> Self {
+        CastleRights::from_sides(false, self.has_queen_side())
+    }
+
+    pub fn without_queen_side(&self) -> Self {
+        CastleRights::from_sides(self.has_king_side(), false)
+    }
+}
+
+/**
+ * State that cannot be deduced back from an action, saved before each move to be restored on undo
+ */
+#[derive(Clone, Copy)]
+struct IrreversibleState {
+    captured: Option<Piece>,
+    double_pawn_move: Option<(usize, usize)>,
+    halfmove_clock: u32,
+    white_castles_right: CastleRights,
+    black_castles_right: CastleRights,
+    hash: u64,
+}
+
+#[derive(Clone)]
 pub struct Board {
     mailbox: [Square; BOARD_SIZE],
-    pub double_pawn_move: Option<(usize, usize)>, // (ghost, pawn)
-    pub history: VecDeque<Box<dyn ChessAction>>,
-    pub turn: u32,
-    pub white_king: usize,
-    pub black_king: usize,
-    pub pieces: [usize; MAX_PIECES_COUNT],
+    double_pawn_move: Option<(usize, usize)>, // (ghost, pawn)
+    history: Vec<Action>,
+    turn: u32,           // Half moves since the start of the game, starting at 1
+    halfmove_clock: u32, // Half moves since the last capture or pawn move
+    previous_states: Vec<IrreversibleState>, // Undo stack, one state per action of the history
+    white_king: usize,
+    black_king: usize,
+    pieces: [usize; MAX_PIECES_COUNT],
     pieces_map: [usize; BOARD_SIZE],
     num_pieces: usize,
     color_to_play: Color,
     black_castles_right: CastleRights,
     white_castles_right: CastleRights,
+    hash: u64, // Zobrist hash of the position, updated along with the board
 }
 
 pub struct BoardIterator<'a> {
@@ -128,21 +249,21 @@ impl<'a> Iterator for PiecesIteraror<'a> {
 }
 
 impl Board {
-    pub fn iter(&self) -> BoardIterator {
+    pub fn iter(&self) -> BoardIterator<'_> {
         BoardIterator {
             index: 0,
             board: self,
         }
     }
 
-    pub fn pieces_iter(&self) -> PiecesIteraror {
+    pub fn pieces_iter(&self) -> PiecesIteraror<'_> {
         PiecesIteraror {
             index: 0,
             board: self,
         }
     }
 
-    pub fn mailbox_iter(&self) -> MailboxIterator {
+    pub fn mailbox_iter(&self) -> MailboxIterator<'_> {
         MailboxIterator {
             index: 0,
             board: self,
@@ -155,7 +276,7 @@ impl Board {
      * Position on the actual board, from 0 to 64
      */
     pub fn piece_at_board_index(&self, position: usize) -> &Option<Piece> {
-        match &self.mailbox[TO_MAILBOX[position] as usize] {
+        match &self.mailbox[TO_MAILBOX[position]] {
             Square::Inside(option) => option,
             Square::Outside => &None,
         }
@@ -168,7 +289,7 @@ impl Board {
         &self.mailbox[position]
     }
 
-    pub fn move_piece(
+    pub(crate) fn move_piece(
         &mut self,
         start: usize,
         end: usize,
@@ -196,20 +317,28 @@ impl Board {
         Ok(option)
     }
 
-    pub fn remove_piece(&mut self, position: usize) -> Option<Piece> {
+    pub(crate) fn remove_piece(&mut self, position: usize) -> Option<Piece> {
         match &mut self.mailbox[position] {
             Square::Inside(ref mut option @ Some(_)) => {
                 let index = self.pieces_map[position];
                 self.pieces[index] = self.pieces[self.num_pieces - 1];
                 self.pieces_map[self.pieces[index]] = index;
                 self.num_pieces -= 1;
-                option.take()
+                let piece = option.take();
+                if let Some(piece) = &piece {
+                    self.hash ^= zobrist::piece_key(piece, position);
+                }
+                piece
             }
             _ => None,
         }
     }
 
-    pub fn add_piece(&mut self, position: usize, piece: Piece) -> Result<(), InvalidRemovalError> {
+    pub(crate) fn add_piece(
+        &mut self,
+        position: usize,
+        piece: Piece,
+    ) -> Result<(), InvalidRemovalError> {
         match &mut self.mailbox[position] {
             Square::Inside(option) => {
                 if option.is_some() {
@@ -221,6 +350,7 @@ impl Board {
                 self.pieces[self.num_pieces] = position;
                 self.pieces_map[position] = self.num_pieces;
                 self.num_pieces += 1;
+                self.hash ^= zobrist::piece_key(&piece, position);
                 *option = Some(piece);
                 Ok(())
             }
@@ -238,92 +368,210 @@ impl Board {
         }
     }
 
-    pub fn do_move(&mut self, mut action: Box<dyn ChessAction>) {
+    /**
+     * Plays the action identified by the key when it is legal, and gives it back.
+     * The board is left untouched when the action is refused
+     */
+    pub fn try_play(&mut self, key: &ActionKey) -> Result<Action, PlayError> {
+        let status = self.status();
+        if status.is_over() {
+            return Err(PlayError::GameOver(status));
+        }
+        match self.mailbox.get(key.start) {
+            Some(Square::Inside(Some(piece))) if *piece.get_color() != self.color_to_play => {
+                return Err(PlayError::WrongSide {
+                    square: key.start,
+                    color: self.color_to_play,
+                })
+            }
+            Some(Square::Inside(Some(_))) => (),
+            _ => return Err(PlayError::NoPiece { square: key.start }),
+        }
+        let action = actions::generate_moves(self)
+            .take(key)
+            .ok_or(PlayError::Illegal(*key))?;
+        self.do_move(action).map_err(PlayError::Invalid)?;
+        Ok(action)
+    }
+
+    /**
+     * Plays an action without checking it, as the engine does with generated actions.
+     * Fails when the pieces on the board do not allow the action
+     */
+    pub fn do_move(&mut self, action: Action) -> Result<(), InvalidMoveError> {
+        let mut state = IrreversibleState {
+            captured: None,
+            double_pawn_move: self.double_pawn_move,
+            halfmove_clock: self.halfmove_clock,
+            white_castles_right: self.white_castles_right,
+            black_castles_right: self.black_castles_right,
+            hash: self.hash,
+        };
+        let resets_clock = matches!(
+            self.mailbox[action.start_square()],
+            Square::Inside(Some(Piece::Pawn { .. }))
+        ) || matches!(
+            self.mailbox[action.target_square()],
+            Square::Inside(Some(_))
+        );
+
+        // Depends on the pieces around the pawn, so it is removed before they move
+        self.hash ^= self.en_passant_key();
         match action.execute(self) {
-            Ok(_) => {
+            Ok(captured) => {
+                state.captured = captured;
+                self.previous_states.push(state);
                 self.double_pawn_move = None;
+                self.halfmove_clock = if resets_clock {
+                    0
+                } else {
+                    self.halfmove_clock + 1
+                };
 
-                match &mut self.mailbox[action.target_square()] {
-                    Square::Inside(ref mut option) => match option.as_mut() {
-                        Some(piece) => match piece {
-                            Piece::Rook {
-                                color: _,
-                                first_move,
-                            } => {
-                                if *first_move >= self.turn - 1 {
-                                    *first_move = self.turn;
-                                }
-                            }
-                            Piece::King { color, first_move } => {
-                                if *first_move >= self.turn - 1 {
-                                    *first_move = self.turn;
-                                }
-
-                                match color {
-                                    Color::WHITE => self.white_king = action.target_square(),
-                                    Color::BLACK => self.black_king = action.target_square(),
-                                }
-                            }
-                            Piece::Pawn { color: _ } => {
-                                self.double_pawn_move = action.double_forward()
-                            }
-                            _ => (),
-                        },
-                        None => (),
+                match self.mailbox[action.target_square()] {
+                    Square::Inside(Some(Piece::King { color })) => match color {
+                        Color::WHITE => self.white_king = action.target_square(),
+                        Color::BLACK => self.black_king = action.target_square(),
                     },
-                    Square::Outside => (),
+                    Square::Inside(Some(Piece::Pawn { .. })) => {
+                        self.double_pawn_move = action.double_forward()
+                    }
+                    _ => (),
                 }
-                self.history.push_back(action);
+                self.revoke_castle_rights(action.start_square());
+                self.revoke_castle_rights(action.target_square());
+                self.history.push(action);
                 self.turn += 1;
+                self.hash ^= zobrist::color_key(&self.color_to_play);
                 self.color_to_play = self.color_to_play.next();
+                self.hash ^= zobrist::color_key(&self.color_to_play) ^ self.en_passant_key();
+                debug_assert_eq!(self.hash, self.compute_hash(), "incremental hash diverged");
+                Ok(())
             }
-            Err(err) => println!("do : {}, action: {:?}, \n{}", err.reason, action, self),
-        }
-    }
-
-    pub fn undo_last_move(&mut self) {
-        match self.history.pop_back() {
-            Some(mut action) => match action.undo(self) {
-                Ok(_) => {
-                    match &mut self.mailbox[action.start_square()] {
-                        Square::Inside(ref mut option) => match option.as_mut() {
-                            Some(piece) => match piece {
-                                Piece::Rook {
-                                    color: _,
-                                    first_move,
-                                } => {
-                                    if *first_move >= self.turn - 1 {
-                                        *first_move = u32::MAX;
-                                    }
-                                }
-                                Piece::King { color, first_move } => {
-                                    if *first_move >= self.turn - 1 {
-                                        *first_move = u32::MAX;
-                                    }
+            Err(err) => {
+                self.hash = state.hash;
+                Err(err)
+            }
+        }
+    }
 
-                                    match color {
-                                        Color::WHITE => self.white_king = action.start_square(),
-                                        Color::BLACK => self.black_king = action.start_square(),
-                                    }
-                                }
-                                _ => (),
-                            },
-                            None => (),
-                        },
-                        Square::Outside => (),
-                    };
-                    self.color_to_play = self.color_to_play.next();
-                    self.turn -= 1;
-                    if let Some(action) = self.history.back() {
-                        self.double_pawn_move = action.double_forward();
-                    } else {
-                        self.double_pawn_move = None;
+    /**
+     * Undoes the last action of the history and gives it back, so that it can be played again.
+     * None when there is no action to undo, or when the board no longer allows undoing it
+     */
+    pub fn undo_last_move(&mut self) -> Option<Action> {
+        let action = self.history.pop()?;
+        let state = self.previous_states.pop()?;
+        match action.undo(self, state.captured) {
+            Ok(_) => {
+                if let Square::Inside(Some(Piece::King { color })) =
+                    self.mailbox[action.start_square()]
+                {
+                    match color {
+                        Color::WHITE => self.white_king = action.start_square(),
+                        Color::BLACK => self.black_king = action.start_square(),
                     }
                 }
-                Err(err) => println!("undo : {}, action : {:?}, \n{}", err.reason, action, self),
-            },
-            None => (),
+                self.color_to_play = self.color_to_play.next();
+                self.turn -= 1;
+                self.double_pawn_move = state.double_pawn_move;
+                self.halfmove_clock = state.halfmove_clock;
+                self.white_castles_right = state.white_castles_right;
+                self.black_castles_right = state.black_castles_right;
+                self.hash = state.hash;
+                debug_assert_eq!(self.hash, self.compute_hash(), "hash not restored on undo");
+                Some(action)
+            }
+            Err(_) => {
+                self.history.push(action);
+                self.previous_states.push(state);
+                None
+            }
+        }
+    }
+
+    /**
+     * Zobrist hash of the position : pieces, side to move, castling rights and en passant
+     */
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    /**
+     * Hash of the position computed from scratch, the incremental one must always be equal
+     */
+    pub fn compute_hash(&self) -> u64 {
+        let pieces = self.pieces_iter().fold(0, |hash, (position, piece)| {
+            hash ^ zobrist::piece_key(piece, position)
+        });
+        pieces
+            ^ zobrist::color_key(&self.color_to_play)
+            ^ zobrist::castle_rights_key(&Color::WHITE, self.white_castles_right)
+            ^ zobrist::castle_rights_key(&Color::BLACK, self.black_castles_right)
+            ^ self.en_passant_key()
+    }
+
+    /**
+     * The en passant square only matters when a pawn is actually able to take
+     */
+    fn en_passant_key(&self) -> u64 {
+        match self.double_pawn_move {
+            Some((ghost, pawn))
+                if [pawn - 1, pawn + 1].iter().any(|side| {
+                    matches!(self.mailbox[*side], Square::Inside(Some(Piece::Pawn { color })) if color == self.color_to_play)
+                }) =>
+            {
+                zobrist::en_passant_key(ghost)
+            }
+            _ => 0,
+        }
+    }
+
+    /**
+     * Number of times the current position occurred, including now.
+     * Positions before the last capture or pawn move cannot repeat
+     */
+    pub fn repetitions(&self) -> usize {
+        1 + self
+            .previous_states
+            .iter()
+            .rev()
+            .take(self.halfmove_clock as usize)
+            .filter(|state| state.hash == self.hash)
+            .count()
+    }
+
+    pub fn castle_rights(&self, color: &Color) -> CastleRights {
+        match color {
+            Color::WHITE => self.white_castles_right,
+            Color::BLACK => self.black_castles_right,
+        }
+    }
+
+    fn set_castle_rights(&mut self, color: &Color, rights: CastleRights) {
+        self.hash ^= zobrist::castle_rights_key(color, self.castle_rights(color))
+            ^ zobrist::castle_rights_key(color, rights);
+        match color {
+            Color::WHITE => self.white_castles_right = rights,
+            Color::BLACK => self.black_castles_right = rights,
+        }
+    }
+
+    /**
+     * Any move from or to the initial square of a king or a rook loses the matching castling rights,
+     * this also covers a rook captured before having moved
+     */
+    fn revoke_castle_rights(&mut self, position: usize) {
+        let (color, rights) = match TO_BOARD[position] {
+            60 => (Color::WHITE, CastleRights::None),
+            63 => (Color::WHITE, self.white_castles_right.without_king_side()),
+            56 => (Color::WHITE, self.white_castles_right.without_queen_side()),
+            4 => (Color::BLACK, CastleRights::None),
+            7 => (Color::BLACK, self.black_castles_right.without_king_side()),
+            0 => (Color::BLACK, self.black_castles_right.without_queen_side()),
+            _ => return,
         };
+        self.set_castle_rights(&color, rights);
     }
 
     pub fn ray(&self, position: usize, direction: i32) -> Option<(usize, &Piece)> {
@@ -352,6 +600,35 @@ impl Board {
         &self.color_to_play
     }
 
+    /**
+     * (ghost, pawn) squares of a pawn that just moved two squares, the ghost being the square
+     * it can be taken en passant on
+     */
+    pub fn double_pawn_move(&self) -> Option<(usize, usize)> {
+        self.double_pawn_move
+    }
+
+    /**
+     * Actions played since the position was set, the last one last
+     */
+    pub fn history(&self) -> &[Action] {
+        &self.history
+    }
+
+    /**
+     * Half moves since the start of the game, starting at 1
+     */
+    pub fn turn(&self) -> u32 {
+        self.turn
+    }
+
+    /**
+     * Half moves since the last capture or pawn move
+     */
+    pub fn halfmove_clock(&self) -> u32 {
+        self.halfmove_clock
+    }
+
     pub fn is_on_pawn_flag(color: &Color, index: usize) -> bool {
         match color {
             Color::WHITE => index / BOARD_X == WHITE_ROW - 1,
@@ -381,19 +658,49 @@ impl Board {
         }
     }
 
+    /**
+     * Rank of a mailbox index, 'x' outside of the board like get_file
+     */
     pub fn get_column(index: usize) -> char {
-        (10 - index / BOARD_X).to_string().chars().nth(0).unwrap()
+        match index / BOARD_X {
+            row @ BLACK_ROW..=WHITE_ROW => char::from_digit((10 - row) as u32, 10).unwrap(),
+            _ => 'x',
+        }
+    }
+
+    /**
+     * Name of a mailbox square, such as "e4"
+     */
+    pub fn square_name(index: usize) -> String {
+        String::from_iter([Board::get_file(index), Board::get_column(index)])
+    }
+
+    /**
+     * Mailbox index of a square name such as "e4", None if the name is not a valid square
+     */
+    pub fn square_from_name(name: &str) -> Option<usize> {
+        let mut chars = name.chars();
+        match (chars.next(), chars.next(), chars.next()) {
+            (Some(file @ 'a'..='h'), Some(rank @ '1'..='8'), None) => {
+                let column = file as usize - 'a' as usize + 1;
+                let row = 10 - rank.to_digit(10).unwrap() as usize;
+                Some(row * BOARD_X + column)
+            }
+            _ => None,
+        }
     }
 
     pub fn empty() -> Self {
         use Square::*;
 
         let mailbox = [(); BOARD_SIZE].map(|_| Outside);
-        Board {
+        let mut board = Board {
             mailbox,
             double_pawn_move: None,
-            history: VecDeque::new(),
+            history: Vec::with_capacity(HISTORY_CAPACITY),
             turn: 1,
+            halfmove_clock: 0,
+            previous_states: Vec::with_capacity(HISTORY_CAPACITY),
             white_king: 0,
             black_king: 0,
             pieces: [(); MAX_PIECES_COUNT].map(|_| 0),
@@ -401,34 +708,68 @@ impl Board {
             num_pieces: 0,
             color_to_play: Color::WHITE,
             white_castles_right: CastleRights::All,
-            black_castles_right: CastleRights::All
-        }
+            black_castles_right: CastleRights::All,
+            hash: 0,
+        };
+        board.hash = board.compute_hash();
+        board
     }
 
-    pub fn set_piece_inside(&mut self, position: usize, piece: Piece) {
+    pub(crate) fn set_piece_inside(&mut self, position: usize, piece: Piece) {
         use Square::*;
         self.mailbox[position] = Inside(None);
-        self.add_piece(position, piece);
+        self.add_piece(position, piece)
+            .expect("the square was just emptied");
+    }
+
+    /**
+     * The placement must describe 8 ranks of exactly 8 squares, so that no piece ends up outside
+     * of the board
+     */
+    fn check_placement(placement: &str) -> Result<(), InvalidBoardErr> {
+        let ranks: Vec<&str> = placement.split('/').collect();
+        if ranks.len() != 8 {
+            return Err(InvalidBoardErr {
+                err: format!("Expected 8 ranks, found {}", ranks.len()),
+            });
+        }
+        for (rank, squares) in ranks.iter().enumerate() {
+            let files: u32 = squares.chars().map(|c| c.to_digit(10).unwrap_or(1)).sum();
+            if files != 8 {
+                return Err(InvalidBoardErr {
+                    err: format!("Rank {} has {} squares instead of 8", 8 - rank, files),
+                });
+            }
+        }
+        Ok(())
     }
 
+    /**
+     * Parses a FEN string : piece placement, side to move, castling rights, en passant square,
+     * halfmove clock and fullmove number. Missing trailing fields default to "w - - 0 1"
+     */
     pub fn from_fen(notation: String) -> Result<Self, InvalidBoardErr> {
+        let mut fields = notation.split_whitespace();
+        let placement = fields.next().ok_or(InvalidBoardErr {
+            err: "Empty FEN string".to_string(),
+        })?;
+        Board::check_placement(placement)?;
         let mut offset: usize = 2 * BOARD_X + 1;
         let mut index: usize = offset;
         let mut white_king = None;
         let mut black_king = None;
         let mut board = Board::empty();
         use Square::*;
-        for (i, c) in notation.chars().into_iter().enumerate() {
+        for (i, c) in placement.chars().enumerate() {
             if index < BOARD_SIZE {
                 match c.to_lowercase().next() {
                     Some(current) => match current {
-                        // TODO : VERIFY KING MOVED
                         'k' => {
                             let color = Board::get_color_fen(c);
 
                             match color {
                                 Color::WHITE => {
-                                    if let None = white_king {
+                                    if white_king.is_none() {
                                         white_king = Some(index)
                                     } else {
                                         return Err(InvalidBoardErr {
@@ -438,7 +779,7 @@ impl Board {
                                     }
                                 }
                                 Color::BLACK => {
-                                    if let None = black_king {
+                                    if black_king.is_none() {
                                         black_king = Some(index)
                                     } else {
                                         return Err(InvalidBoardErr {
@@ -452,7 +793,6 @@ impl Board {
                                 index,
                                 Piece::King {
                                     color: Board::get_color_fen(c),
-                                    first_move: u32::MAX,
                                 },
                             )
                         }
@@ -462,12 +802,10 @@ impl Board {
                                 color: Board::get_color_fen(c),
                             },
                         ),
-                        // TODO : VERIFY TOWER MOVED
                         'r' => board.set_piece_inside(
                             index,
                             Piece::Rook {
                                 color: Board::get_color_fen(c),
-                                first_move: u32::MAX,
                             },
                         ),
                         'b' => board.set_piece_inside(
@@ -493,12 +831,12 @@ impl Board {
                             for i in 0..=empty_size {
                                 board.mailbox[index + i] = Inside(None)
                             }
-                            index += empty_size as usize;
+                            index += empty_size;
                         }
                         '/' => {
-                            if (index - offset) % 8 != 0 {
+                            if !(index - offset).is_multiple_of(8) {
                                 return Err(InvalidBoardErr {
-                                    err: String::from(format!("Invalid return at index {}", i)),
+                                    err: format!("Invalid return at index {}", i),
                                 });
                             }
                             index += 1;
@@ -506,28 +844,341 @@ impl Board {
                         }
                         _ => {
                             return Err(InvalidBoardErr {
-                                err: String::from(format!(
+                                err: format!(
                                     "Could not identify the character {} at index {}",
                                     c, i
-                                )),
+                                ),
                             })
                         }
                     },
                     None => {
                         return Err(InvalidBoardErr {
-                            err: String::from(format!(
-                                "Could not identify the character {} at index {}",
-                                c, i
-                            )),
+                            err: format!("Could not identify the character {} at index {}", c, i),
                         })
                     }
                 };
             }
             index += 1;
         }
-        board.double_pawn_move = None;
-        board.white_king = white_king.unwrap();
-        board.black_king = black_king.unwrap();
+        board.white_king = white_king.ok_or(InvalidBoardErr {
+            err: "No white king was found on the board".to_string(),
+        })?;
+        board.black_king = black_king.ok_or(InvalidBoardErr {
+            err: "No black king was found on the board".to_string(),
+        })?;
+
+        board.color_to_play = match fields.next().unwrap_or("w") {
+            "w" => Color::WHITE,
+            "b" => Color::BLACK,
+            other => {
+                return Err(InvalidBoardErr {
+                    err: format!("Invalid side to move '{}'", other),
+                })
+            }
+        };
+
+        let castles = fields.next().unwrap_or("-");
+        if castles != "-" && (castles.is_empty() || !castles.chars().all(|c| "KQkq".contains(c))) {
+            return Err(InvalidBoardErr {
+                err: format!("Invalid castling rights '{}'", castles),
+            });
+        }
+        board.white_castles_right =
+            CastleRights::from_sides(castles.contains('K'), castles.contains('Q'));
+        board.black_castles_right =
+            CastleRights::from_sides(castles.contains('k'), castles.contains('q'));
+
+        board.double_pawn_move = match fields.next().unwrap_or("-") {
+            "-" => None,
+            square => {
+                let ghost = Board::square_from_name(square).ok_or(InvalidBoardErr {
+                    err: format!("Invalid en passant square '{}'", square),
+                })?;
+                // The pawn that moved two squares belongs to the side that just played
+                let (ghost_row, pawn) = match board.color_to_play {
+                    Color::WHITE => (BLACK_ROW + 2, ghost + BOARD_X),
+                    Color::BLACK => (WHITE_ROW - 2, ghost - BOARD_X),
+                };
+                if ghost / BOARD_X != ghost_row {
+                    return Err(InvalidBoardErr {
+                        err: format!("En passant square '{}' is on the wrong rank", square),
+                    });
+                }
+                let moved_pawn = Piece::Pawn {
+                    color: board.color_to_play.next(),
+                };
+                let pawn_moved = matches!(board.mailbox[ghost], Square::Inside(None))
+                    && matches!(board.mailbox[pawn], Square::Inside(Some(piece)) if piece == moved_pawn);
+                if !pawn_moved {
+                    return Err(InvalidBoardErr {
+                        err: format!(
+                            "En passant square '{}' is not behind a pawn that just moved two squares",
+                            square
+                        ),
+                    });
+                }
+                Some((ghost, pawn))
+            }
+        };
+
+        board.halfmove_clock = Board::parse_fen_number(fields.next(), 0, "halfmove clock")?;
+        let fullmove = Board::parse_fen_number(fields.next(), 1, "fullmove number")?;
+        if fullmove == 0 {
+            return Err(InvalidBoardErr {
+                err: "The fullmove number starts at 1".to_string(),
+            });
+        }
+        board.turn = 2 * fullmove - 1
+            + match board.color_to_play {
+                Color::WHITE => 0,
+                Color::BLACK => 1,
+            };
+
+        if let Some(extra) = fields.next() {
+            return Err(InvalidBoardErr {
+                err: format!("Unexpected trailing FEN field '{}'", extra),
+            });
+        }
+        board.hash = board.compute_hash();
         Ok(board)
     }
+
+    fn parse_fen_number(
+        field: Option<&str>,
+        default: u32,
+        name: &str,
+    ) -> Result<u32, InvalidBoardErr> {
+        match field {
+            Some(value) => value.parse::<u32>().map_err(|_| InvalidBoardErr {
+                err: format!("Invalid {} '{}'", name, value),
+            }),
+            None => Ok(default),
+        }
+    }
+
+    /**
+     * Serializes the board as a complete FEN string
+     */
+    pub fn to_fen(&self) -> String {
+        let mut fen = String::new();
+        let mut empty = 0;
+        for (index, square) in self.iter() {
+            match square {
+                Some(piece) => {
+                    if empty > 0 {
+                        fen.push_str(&empty.to_string());
+                        empty = 0;
+                    }
+                    fen.push(piece.to_fen_char());
+                }
+                None => empty += 1,
+            }
+            if index % BOARD_X == 8 {
+                if empty > 0 {
+                    fen.push_str(&empty.to_string());
+                    empty = 0;
+                }
+                if index / BOARD_X != WHITE_ROW {
+                    fen.push('/');
+                }
+            }
+        }
+
+        fen.push_str(match self.color_to_play {
+            Color::WHITE => " w ",
+            Color::BLACK => " b ",
+        });
+
+        let castles = [
+            (self.white_castles_right.has_king_side(), 'K'),
+            (self.white_castles_right.has_queen_side(), 'Q'),
+            (self.black_castles_right.has_king_side(), 'k'),
+            (self.black_castles_right.has_queen_side(), 'q'),
+        ]
+        .iter()
+        .filter(|(right, _)| *right)
+        .map(|(_, c)| *c)
+        .collect::<String>();
+        fen.push_str(if castles.is_empty() { "-" } else { &castles });
+
+        match self.double_pawn_move {
+            Some((ghost, _)) => fen.push_str(&format!(" {} ", Board::square_name(ghost))),
+            None => fen.push_str(" - "),
+        }
+
+        fen.push_str(&format!(
+            "{} {}",
+            self.halfmove_clock,
+            self.turn.div_ceil(2)
+        ));
+        fen
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Board, PlayError, START_FEN, TO_MAILBOX};
+    use crate::model::{
+        actions::{Action, ActionKey, ActionKind},
+        fixtures::board,
+        piece::Color,
+        status::GameStatus,
+    };
+
+    /**
+     * Tries the move, checking that a refused one leaves the board as it was
+     */
+    fn try_play(board: &mut Board, notation: &str) -> Result<(), PlayError> {
+        let key = ActionKey::from_long_algebraic(notation, board.color_turn()).unwrap();
+        let (fen, hash) = (board.to_fen(), board.hash());
+        let result = board.try_play(&key).map(|_| ());
+        if result.is_err() {
+            assert_eq!(board.to_fen(), fen);
+            assert_eq!(board.hash(), hash);
+        }
+        result
+    }
+
+    #[test]
+    fn reads_and_writes_fen() {
+        for fen in [
+            START_FEN,
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "rnbqkbnr/pp1ppppp/8/2pP4/8/8/PPP1PPPP/RNBQKBNR w Kq c6 0 3",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 b - - 12 40",
+        ] {
+            assert_eq!(board(fen).to_fen(), fen);
+        }
+    }
+
+    #[test]
+    fn refuses_short_and_long_placements() {
+        for fen in [
+            "4k3/8/4K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/8/4K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/4K3p w - - 0 1",
+            "4k3/8/8/8/8/8/8/4K2 w - - 0 1",
+            "4k3/9/8/8/8/8/8/4K3 w - - 0 1",
+        ] {
+            assert!(Board::from_fen(fen.to_string()).is_err(), "{}", fen);
+        }
+    }
+
+    #[test]
+    fn refuses_en_passant_squares_without_a_pawn() {
+        for fen in [
+            "4k3/8/8/4P3/8/8/8/4K3 w - d6 0 1",
+            "4k3/8/3p4/3pP3/8/8/8/4K3 w - d6 0 1",
+            "4k3/8/8/3PP3/8/8/8/4K3 w - d6 0 1",
+            "4k3/8/8/8/4pP2/8/8/4K3 b - e3 0 1",
+        ] {
+            assert!(Board::from_fen(fen.to_string()).is_err(), "{}", fen);
+        }
+        assert!(Board::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1".to_string()).is_ok());
+        assert!(Board::from_fen("4k3/8/8/8/4pP2/8/8/4K3 b - f3 0 1".to_string()).is_ok());
+    }
+
+    #[test]
+    fn refuses_actions_the_board_does_not_allow() {
+        let fen = "4k3/8/8/4P3/8/8/8/4K3 w - - 0 1";
+        let mut board = board(fen);
+        // e5xd6 en passant without any black pawn on d5
+        let action = Action::new(TO_MAILBOX[28], TO_MAILBOX[19], ActionKind::EnPassant);
+        assert!(board.do_move(action).is_err());
+        assert_eq!(board.to_fen(), fen);
+        assert!(board.history.is_empty());
+        assert_eq!(board.undo_last_move(), None);
+    }
+
+    #[test]
+    fn hashes_transpositions_alike() {
+        let mut knights_first = board(START_FEN);
+        let mut queen_knight_first = board(START_FEN);
+        for notation in ["g1f3", "g8f6", "b1c3"] {
+            try_play(&mut knights_first, notation).unwrap();
+        }
+        for notation in ["b1c3", "g8f6", "g1f3"] {
+            try_play(&mut queen_knight_first, notation).unwrap();
+        }
+        assert_eq!(knights_first.to_fen(), queen_knight_first.to_fen());
+        assert_eq!(knights_first.hash(), queen_knight_first.hash());
+        assert_eq!(knights_first.hash(), board(&knights_first.to_fen()).hash());
+        assert_ne!(knights_first.hash(), board(START_FEN).hash());
+    }
+
+    #[test]
+    fn restores_the_hash_on_undo() {
+        for (fen, notation) in [
+            (
+                "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+                "e1g1",
+            ),
+            ("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1", "e8c8"),
+            ("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5d6"),
+            ("4k3/P7/8/8/8/8/8/4K3 w - - 0 1", "a7a8q"),
+            ("1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1", "a7b8n"),
+        ] {
+            let mut position = board(fen);
+            let hash = position.hash();
+            try_play(&mut position, notation).unwrap();
+            assert_ne!(position.hash(), hash, "{}", fen);
+            assert_eq!(position.hash(), board(&position.to_fen()).hash(), "{}", fen);
+            position.undo_last_move();
+            assert_eq!(position.hash(), hash, "{}", fen);
+            assert_eq!(position.hash(), position.compute_hash(), "{}", fen);
+        }
+    }
+
+    #[test]
+    fn plays_legal_moves() {
+        let mut board = board(START_FEN);
+        assert_eq!(try_play(&mut board, "e2e4"), Ok(()));
+        assert_eq!(try_play(&mut board, "e7e5"), Ok(()));
+        assert_eq!(
+            board.to_fen(),
+            "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2"
+        );
+    }
+
+    #[test]
+    fn refuses_illegal_moves() {
+        let mut board = board(START_FEN);
+        let e3 = Board::square_from_name("e3").unwrap();
+        let e7 = Board::square_from_name("e7").unwrap();
+        assert_eq!(
+            try_play(&mut board, "e3e4"),
+            Err(PlayError::NoPiece { square: e3 })
+        );
+        assert_eq!(
+            try_play(&mut board, "e7e5"),
+            Err(PlayError::WrongSide {
+                square: e7,
+                color: Color::WHITE
+            })
+        );
+        assert!(matches!(
+            try_play(&mut board, "e2e5"),
+            Err(PlayError::Illegal(_))
+        ));
+    }
+
+    #[test]
+    fn refuses_pinned_piece() {
+        let mut board = board("4k3/4r3/8/8/8/8/4N3/4K3 w - - 0 1");
+        assert!(matches!(
+            try_play(&mut board, "e2c3"),
+            Err(PlayError::Illegal(_))
+        ));
+    }
+
+    #[test]
+    fn refuses_moves_after_the_end() {
+        let mut board = board("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3");
+        assert_eq!(
+            try_play(&mut board, "e1f2"),
+            Err(PlayError::GameOver(GameStatus::Checkmate {
+                winner: Color::BLACK
+            }))
+        );
+    }
 }
@@ -1,56 +1,28 @@
-use crate::model::{
-    actions::{ChessAction, MovesList},
-    board::{Board, InvalidMoveError},
-    piece::Color,
-};
+use crate::model::board::{Board, InvalidMoveError};
 
-use super::movement::Move;
-#[derive(Debug)]
-pub struct Castle {
-    pub king: Move,
-    pub rook: Move,
-}
-impl Castle {
-    pub fn new(king: Move, rook: Move) -> Self {
-        Castle { king, rook }
+/**
+ * Rook squares of a castle, given by the two squares move of the king
+ */
+fn rook_move(king_start: usize, king_end: usize) -> (usize, usize) {
+    if king_end > king_start {
+        (king_start + 3, king_start + 1)
+    } else {
+        (king_start - 4, king_start - 1)
     }
 }
-impl ChessAction for Castle {
-    fn execute(&mut self, board: &mut Board) -> Result<(), InvalidMoveError> {
-        board.move_piece(self.king.start, self.king.end)?;
-        board.move_piece(self.rook.start, self.rook.end)?;
-
-        Ok(())
-    }
-
-    fn undo(&mut self, board: &mut Board) -> Result<(), InvalidMoveError> {
-        board.move_piece(self.king.end, self.king.start)?;
-        board.move_piece(self.rook.end, self.rook.start)?;
 
-        Ok(())
-    }
-
-    fn as_promotion(&self, color: &Color) -> Result<MovesList, String> {
-        Err("Cannot call 'as_promotion' on move 'Castle'".to_string())
-    }
+pub fn execute(board: &mut Board, start: usize, end: usize) -> Result<(), InvalidMoveError> {
+    let (rook_start, rook_end) = rook_move(start, end);
+    board.move_piece(start, end)?;
+    board.move_piece(rook_start, rook_end)?;
 
-    fn to_algebraic_notation(&self, board: &Board) -> String {
-        if (self.king.start % 8 > self.rook.start) {
-            String::from("OwOwO")
-        } else {
-            String::from("OwO")
-        }
-    }
-
-    fn target_square(&self) -> usize {
-        self.king.end
-    }
+    Ok(())
+}
 
-    fn start_square(&self) -> usize {
-        self.king.start
-    }
+pub fn undo(board: &mut Board, start: usize, end: usize) -> Result<(), InvalidMoveError> {
+    let (rook_start, rook_end) = rook_move(start, end);
+    board.move_piece(end, start)?;
+    board.move_piece(rook_end, rook_start)?;
 
-    fn double_forward(&self) -> Option<(usize, usize)> {
-        None
-    }
+    Ok(())
 }
@@ -1,51 +1,42 @@
 use crate::model::{
-    actions::{ChessAction, MovesList},
     board::{Board, InvalidMoveError},
     piece::{Color, Piece},
 };
-#[derive(Debug)]
-pub struct Promote {
-    pub piece: Piece,
-    pub previous_action: Box<dyn ChessAction>,
-}
 
-impl Promote {
-    pub fn new(piece: Piece, previous_action: Box<dyn ChessAction>) -> Self {
-        Promote {
-            piece,
-            previous_action,
-        }
-    }
+/**
+ * Replaces the pawn that reached the last row, once it moved
+ */
+pub fn execute(
+    board: &mut Board,
+    start: usize,
+    end: usize,
+    piece: Piece,
+) -> Result<(), InvalidMoveError> {
+    board.remove_piece(end);
+    board
+        .add_piece(end, piece)
+        .map_err(|error| InvalidMoveError {
+            start,
+            end,
+            reason: error.reason,
+        })
 }
 
-impl ChessAction for Promote {
-    fn execute(&mut self, board: &mut Board) -> Result<(), InvalidMoveError> {
-        todo!()
-        //TODO : Set rook moved
-    }
-
-    fn undo(&mut self, board: &mut Board) -> Result<(), InvalidMoveError> {
-        //TODO : Unset rook moved
-        todo!()
-    }
-
-    fn as_promotion(&self, _color: &Color) -> Result<MovesList, String> {
-        Err("Cannot call 'as_promotion' on move 'Promotion'".to_string())
-    }
-
-    fn to_algebraic_notation(&self, board: &Board) -> String {
-        "promote".to_string()
-    }
-
-    fn target_square(&self) -> usize {
-        self.previous_action.target_square()
-    }
-
-    fn start_square(&self) -> usize {
-        self.previous_action.start_square()
-    }
-
-    fn double_forward(&self) -> Option<(usize, usize)> {
-        None
-    }
+/**
+ * Puts the pawn back, before the move itself is undone
+ */
+pub fn undo(
+    board: &mut Board,
+    start: usize,
+    end: usize,
+    color: &Color,
+) -> Result<(), InvalidMoveError> {
+    board.remove_piece(end);
+    board
+        .add_piece(end, Piece::Pawn { color: *color })
+        .map_err(|error| InvalidMoveError {
+            start,
+            end,
+            reason: error.reason,
+        })
 }
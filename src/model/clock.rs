@@ -0,0 +1,265 @@
+use std::{fmt, str::FromStr, time::Duration};
+
+use super::piece::Color;
+
+/**
+ * Time given back on each move before the clock really runs
+ */
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum Delay {
+    None,
+    /**
+     * The clock waits for the delay before it starts running
+     */
+    Simple(Duration),
+    /**
+     * The clock runs from the start, the time used is given back up to the delay once the move is
+     * played
+     */
+    Bronstein(Duration),
+}
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct TimeControl {
+    pub base: Duration,
+    pub increment: Duration,
+    pub delay: Delay,
+}
+
+impl TimeControl {
+    /**
+     * Value of the PGN TimeControl tag : seconds of the base time and of the increment.
+     * The tag has no field for a delay, None when there is one
+     */
+    pub fn to_pgn_tag(&self) -> Option<String> {
+        if self.delay != Delay::None {
+            return None;
+        }
+        if self.increment.is_zero() {
+            Some(to_seconds(self.base))
+        } else {
+            Some(format!(
+                "{}+{}",
+                to_seconds(self.base),
+                to_seconds(self.increment)
+            ))
+        }
+    }
+}
+
+/**
+ * Seconds with up to three decimals, without trailing zeros
+ */
+fn to_seconds(time: Duration) -> String {
+    match time.subsec_millis() {
+        0 => format!("{}", time.as_secs()),
+        millis => format!("{}.{:03}", time.as_secs(), millis)
+            .trim_end_matches('0')
+            .to_string(),
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct InvalidTimeControlErr {
+    pub err: String,
+}
+
+impl fmt::Display for InvalidTimeControlErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid time control : {}", self.err)
+    }
+}
+
+/**
+ * Reads <minutes>[+<increment seconds>], such as 5, 3+2 or 0.5+1, without any delay
+ */
+impl FromStr for TimeControl {
+    type Err = InvalidTimeControlErr;
+
+    fn from_str(notation: &str) -> Result<Self, Self::Err> {
+        let (base, increment) = notation.split_once('+').unwrap_or((notation, "0"));
+        let seconds = |value: &str, factor: f64| {
+            value
+                .parse::<f64>()
+                .ok()
+                .filter(|value| value.is_finite() && *value >= 0.0)
+                .map(|value| Duration::from_secs_f64(value * factor))
+                .ok_or(InvalidTimeControlErr {
+                    err: format!("'{}' is not a number of minutes or seconds", value),
+                })
+        };
+        let base = seconds(base, 60.0)?;
+        if base.is_zero() {
+            return Err(InvalidTimeControlErr {
+                err: "the base time must not be zero".to_string(),
+            });
+        }
+        Ok(TimeControl {
+            base,
+            increment: seconds(increment, 1.0)?,
+            delay: Delay::None,
+        })
+    }
+}
+
+/**
+ * Time left to each side. The clock is only told how long each move took, the caller measures it
+ */
+#[derive(Clone, Copy, Debug)]
+pub struct Clock {
+    pub control: TimeControl,
+    remaining: [Duration; 2],
+}
+
+fn side(color: &Color) -> usize {
+    match color {
+        Color::WHITE => 0,
+        Color::BLACK => 1,
+    }
+}
+
+impl Clock {
+    pub fn new(control: TimeControl) -> Self {
+        Clock {
+            control,
+            remaining: [control.base; 2],
+        }
+    }
+
+    /**
+     * Time left once the last move of the side was played
+     */
+    pub fn remaining(&self, color: &Color) -> Duration {
+        self.remaining[side(color)]
+    }
+
+    pub fn set_remaining(&mut self, color: &Color, remaining: Duration) {
+        self.remaining[side(color)] = remaining;
+    }
+
+    /**
+     * Time shown on the clock of the side after thinking for `spent` on its move
+     */
+    pub fn running(&self, color: &Color, spent: Duration) -> Duration {
+        let charged = match self.control.delay {
+            Delay::Simple(delay) => spent.saturating_sub(delay),
+            Delay::None | Delay::Bronstein(_) => spent,
+        };
+        self.remaining(color).saturating_sub(charged)
+    }
+
+    /**
+     * The flag falls when the clock reaches zero before the move is played
+     */
+    pub fn is_flagged(&self, color: &Color, spent: Duration) -> bool {
+        self.running(color, spent).is_zero()
+    }
+
+    /**
+     * Stops the clock of the side which played in `spent`, adding the increment and the Bronstein
+     * delay, and returns its time left
+     */
+    pub fn press(&mut self, color: &Color, spent: Duration) -> Duration {
+        let given_back = match self.control.delay {
+            Delay::Bronstein(delay) => spent.min(delay),
+            Delay::None | Delay::Simple(_) => Duration::ZERO,
+        };
+        let remaining = self.running(color, spent) + given_back + self.control.increment;
+        self.set_remaining(color, remaining);
+        remaining
+    }
+}
+
+/**
+ * Time as written in the %clk command of PGN comments : h:mm:ss
+ */
+pub fn to_clk(time: Duration) -> String {
+    let seconds = time.as_secs();
+    format!(
+        "{}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn control(delay: Delay) -> TimeControl {
+        TimeControl {
+            base: Duration::from_secs(60),
+            increment: Duration::ZERO,
+            delay,
+        }
+    }
+
+    #[test]
+    fn reads_time_controls() {
+        let blitz: TimeControl = "3+2".parse().unwrap();
+        assert_eq!(blitz.base, Duration::from_secs(180));
+        assert_eq!(blitz.increment, Duration::from_secs(2));
+        assert_eq!(blitz.to_pgn_tag().unwrap(), "180+2");
+
+        let bullet: TimeControl = "0.5".parse().unwrap();
+        assert_eq!(bullet.base, Duration::from_secs(30));
+        assert_eq!(bullet.to_pgn_tag().unwrap(), "30");
+
+        let fractional: TimeControl = "0.01+0.5".parse().unwrap();
+        assert_eq!(fractional.to_pgn_tag().unwrap(), "0.6+0.5");
+        let delayed = TimeControl {
+            delay: Delay::Bronstein(Duration::from_secs(2)),
+            ..blitz
+        };
+        assert_eq!(delayed.to_pgn_tag(), None);
+
+        assert!("0+2".parse::<TimeControl>().is_err());
+        assert!("3+x".parse::<TimeControl>().is_err());
+        assert!("-1".parse::<TimeControl>().is_err());
+    }
+
+    #[test]
+    fn adds_the_increment() {
+        let mut clock = Clock::new(TimeControl {
+            increment: Duration::from_secs(2),
+            ..control(Delay::None)
+        });
+        let remaining = clock.press(&Color::WHITE, Duration::from_secs(5));
+        assert_eq!(remaining, Duration::from_secs(57));
+        assert_eq!(clock.remaining(&Color::BLACK), Duration::from_secs(60));
+    }
+
+    #[test]
+    fn delays_the_clock() {
+        let spent = Duration::from_secs(3);
+        let mut simple = Clock::new(control(Delay::Simple(Duration::from_secs(5))));
+        assert_eq!(
+            simple.running(&Color::WHITE, spent),
+            Duration::from_secs(60)
+        );
+        assert_eq!(simple.press(&Color::WHITE, spent), Duration::from_secs(60));
+
+        let mut bronstein = Clock::new(control(Delay::Bronstein(Duration::from_secs(5))));
+        assert_eq!(
+            bronstein.running(&Color::WHITE, spent),
+            Duration::from_secs(57)
+        );
+        assert_eq!(
+            bronstein.press(&Color::WHITE, spent),
+            Duration::from_secs(60)
+        );
+        assert_eq!(
+            bronstein.press(&Color::WHITE, Duration::from_secs(8)),
+            Duration::from_secs(57)
+        );
+    }
+
+    #[test]
+    fn falls_the_flag() {
+        let clock = Clock::new(control(Delay::Simple(Duration::from_secs(5))));
+        assert!(!clock.is_flagged(&Color::BLACK, Duration::from_secs(64)));
+        assert!(clock.is_flagged(&Color::BLACK, Duration::from_secs(65)));
+        assert_eq!(to_clk(Duration::from_secs(3723)), "1:02:03");
+    }
+}
@@ -0,0 +1,21 @@
+/*!
+ * Positions and moves shared by the tests of the model, the generator and the protocols
+ */
+use super::{actions::ActionKey, board::Board};
+
+/**
+ * Board of a FEN the test knows to be valid
+ */
+pub fn board(fen: &str) -> Board {
+    Board::from_fen(fen.to_string()).unwrap_or_else(|e| panic!("{}", e))
+}
+
+/**
+ * Plays a move in long algebraic notation the test knows to be legal
+ */
+pub fn play(board: &mut Board, notation: &str) {
+    let key = ActionKey::from_long_algebraic(notation, board.color_turn()).unwrap();
+    if let Err(error) = board.try_play(&key) {
+        panic!("{} : {}", notation, error);
+    }
+}
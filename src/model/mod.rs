@@ -1,5 +1,13 @@
 pub mod actions;
 pub mod algebraic_notation;
+pub mod bitboard;
 pub mod board;
 pub mod chess_actions;
+pub mod clock;
+#[cfg(test)]
+pub(crate) mod fixtures;
+pub mod pgn;
 pub mod piece;
+pub mod position;
+pub mod status;
+pub mod zobrist;
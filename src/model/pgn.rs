@@ -0,0 +1,507 @@
+use std::{fmt, time::Duration};
+
+use super::{
+    actions::Action,
+    algebraic_notation::{self, AlgebraicNotationError},
+    board::{Board, InvalidBoardErr, START_FEN},
+    clock,
+};
+
+const SEVEN_TAG_ROSTER: [(&str, &str); 7] = [
+    ("Event", "?"),
+    ("Site", "?"),
+    ("Date", "????.??.??"),
+    ("Round", "?"),
+    ("White", "?"),
+    ("Black", "?"),
+    ("Result", "*"),
+];
+const RESULTS: [&str; 4] = ["1-0", "0-1", "1/2-1/2", "*"];
+const MAX_LINE_LENGTH: usize = 79;
+
+#[derive(Debug)]
+pub enum PgnError {
+    Syntax(String),
+    InvalidFen(String),
+    InvalidMove {
+        ply: usize,
+        error: AlgebraicNotationError,
+    },
+}
+
+impl fmt::Display for PgnError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PgnError::Syntax(reason) => write!(f, "Invalid PGN : {}", reason),
+            PgnError::InvalidFen(reason) => write!(f, "Invalid FEN tag : {}", reason),
+            PgnError::InvalidMove { ply, error } => write!(f, "Ply {} : {}", ply, error),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PgnMove {
+    pub san: String,
+    pub comment: Option<String>,
+}
+
+/**
+ * A game of a PGN file : its tags, the SAN moves of the main line and the result.
+ * Variations, NAGs and annotation glyphs written apart from their move are skipped when
+ * reading
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct PgnGame {
+    pub tags: Vec<(String, String)>,
+    pub moves: Vec<PgnMove>,
+    pub result: String,
+}
+
+impl PgnGame {
+    /**
+     * Empty game with the Seven Tag Roster filled with unknown values
+     */
+    pub fn new() -> Self {
+        PgnGame {
+            tags: SEVEN_TAG_ROSTER
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            moves: Vec::new(),
+            result: "*".to_string(),
+        }
+    }
+
+    fn untagged() -> Self {
+        PgnGame {
+            tags: Vec::new(),
+            ..PgnGame::new()
+        }
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(tag, _)| tag == name) {
+            Some((_, current)) => *current = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    pub fn set_result(&mut self, result: &str) {
+        self.result = result.to_string();
+        self.set_tag("Result", result);
+    }
+
+    /**
+     * Builds the game from the history of the board, which is left in the same state
+     */
+    pub fn from_board(board: &mut Board) -> Self {
+        let mut undone: Vec<Action> = Vec::new();
+        while let Some(action) = board.undo_last_move() {
+            undone.push(action);
+        }
+
+        let mut game = PgnGame::new();
+        let start_fen = board.to_fen();
+        if start_fen != START_FEN {
+            game.set_tag("SetUp", "1");
+            game.set_tag("FEN", &start_fen);
+        }
+
+        while let Some(action) = undone.pop() {
+            game.moves.push(PgnMove {
+                san: algebraic_notation::to_algebraic_notation(board, &action),
+                comment: None,
+            });
+            board
+                .do_move(action)
+                .expect("an action of the history could not be played again");
+        }
+        game.set_result(board.status().result());
+        game
+    }
+
+    /**
+     * Writes the time left after each move as a %clk command in its comment
+     */
+    pub fn set_clocks(&mut self, clocks: &[Duration]) {
+        for (pgn_move, clock) in self.moves.iter_mut().zip(clocks) {
+            pgn_move.comment = Some(format!("[%clk {}]", clock::to_clk(*clock)));
+        }
+    }
+
+    /**
+     * Plays the main line from the starting position, or from the FEN tag when there is one
+     */
+    pub fn to_board(&self) -> Result<Board, PgnError> {
+        let fen = self.tag("FEN").unwrap_or(START_FEN);
+        let mut board = Board::from_fen(fen.to_string())
+            .map_err(|InvalidBoardErr { err }| PgnError::InvalidFen(err))?;
+        for (index, pgn_move) in self.moves.iter().enumerate() {
+            let invalid_move = |error| PgnError::InvalidMove {
+                ply: index + 1,
+                error,
+            };
+            let action = algebraic_notation::from_algebraic_notation(&board, &pgn_move.san)
+                .map_err(invalid_move)?;
+            board
+                .do_move(action)
+                .map_err(|_| invalid_move(AlgebraicNotationError::Illegal(pgn_move.san.clone())))?;
+        }
+        Ok(board)
+    }
+
+    fn write_movetext(&self) -> String {
+        let fen = self.tag("FEN").unwrap_or(START_FEN);
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        let mut white_to_play = fields.get(1).is_none_or(|side| *side != "b");
+        let mut move_number = fields
+            .get(5)
+            .and_then(|number| number.parse::<u32>().ok())
+            .unwrap_or(1);
+
+        let mut tokens: Vec<String> = Vec::new();
+        let mut needs_number = true;
+        for pgn_move in self.moves.iter() {
+            if white_to_play {
+                tokens.push(format!("{}.", move_number));
+            } else if needs_number {
+                tokens.push(format!("{}...", move_number));
+            }
+            tokens.push(pgn_move.san.clone());
+            needs_number = false;
+            if let Some(comment) = &pgn_move.comment {
+                tokens.push(format!("{{{}}}", comment));
+                needs_number = true;
+            }
+            if !white_to_play {
+                move_number += 1;
+            }
+            white_to_play = !white_to_play;
+        }
+        tokens.push(self.result.clone());
+
+        let mut movetext = String::new();
+        let mut line_length = 0;
+        for token in tokens {
+            if line_length > 0 && line_length + 1 + token.len() > MAX_LINE_LENGTH {
+                movetext.push('\n');
+                line_length = 0;
+            } else if line_length > 0 {
+                movetext.push(' ');
+                line_length += 1;
+            }
+            line_length += token.len();
+            movetext.push_str(&token);
+        }
+        movetext
+    }
+}
+
+impl Default for PgnGame {
+    fn default() -> Self {
+        PgnGame::new()
+    }
+}
+
+impl fmt::Display for PgnGame {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (name, value) in self.tags.iter() {
+            writeln!(
+                f,
+                "[{} \"{}\"]",
+                name,
+                value.replace('\\', "\\\\").replace('"', "\\\"")
+            )?;
+        }
+        writeln!(f)?;
+        writeln!(f, "{}", self.write_movetext())
+    }
+}
+
+enum Token {
+    Tag(String, String),
+    Comment(String),
+    VariationStart,
+    VariationEnd,
+    Nag,
+    Symbol(String),
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, PgnError> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    let mut line_start = true;
+    while let Some(c) = chars.next() {
+        let previous_line_start = line_start;
+        line_start = c == '\n';
+        match c {
+            // Escape mechanism : the whole line is ignored
+            '%' if previous_line_start => {
+                // take_while consumes the end of line too
+                chars.by_ref().take_while(|c| *c != '\n').for_each(drop);
+                line_start = true;
+            }
+            ';' => {
+                let comment: String = chars.by_ref().take_while(|c| *c != '\n').collect();
+                tokens.push(Token::Comment(comment.trim().to_string()));
+                line_start = true;
+            }
+            '{' => {
+                let mut comment = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => comment.push(c),
+                        None => return Err(PgnError::Syntax("unterminated comment".to_string())),
+                    }
+                }
+                tokens.push(Token::Comment(comment.trim().to_string()));
+            }
+            '[' => {
+                // A ']' inside the quoted value does not close the tag
+                let mut tag = String::new();
+                let mut quoted = false;
+                loop {
+                    match chars.next() {
+                        Some(']') if !quoted => break,
+                        Some('"') => {
+                            quoted = !quoted;
+                            tag.push('"');
+                        }
+                        Some('\\') if quoted => {
+                            tag.push('\\');
+                            tag.extend(chars.next());
+                        }
+                        Some(c) => tag.push(c),
+                        None => return Err(PgnError::Syntax("unterminated tag".to_string())),
+                    }
+                }
+                tokens.push(parse_tag(&tag)?);
+            }
+            '(' => tokens.push(Token::VariationStart),
+            ')' => tokens.push(Token::VariationEnd),
+            '$' => {
+                while chars.peek().is_some_and(|c| c.is_ascii_digit()) {
+                    chars.next();
+                }
+                tokens.push(Token::Nag);
+            }
+            c if c.is_whitespace() => (),
+            c => {
+                let mut symbol = c.to_string();
+                while let Some(next) = chars.peek() {
+                    if next.is_whitespace() || "{}()[];$".contains(*next) {
+                        break;
+                    }
+                    symbol.push(*next);
+                    chars.next();
+                }
+                tokens.push(Token::Symbol(symbol));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+fn parse_tag(tag: &str) -> Result<Token, PgnError> {
+    let tag = tag.trim();
+    let (name, value) = tag
+        .split_once(char::is_whitespace)
+        .ok_or(PgnError::Syntax(format!("invalid tag [{}]", tag)))?;
+    let value = value.trim();
+    if value.len() < 2 || !value.starts_with('"') || !value.ends_with('"') {
+        return Err(PgnError::Syntax(format!("invalid tag value [{}]", tag)));
+    }
+    let mut unescaped = String::new();
+    let mut chars = value[1..value.len() - 1].chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unescaped.extend(chars.next()),
+            c => unescaped.push(c),
+        }
+    }
+    Ok(Token::Tag(name.to_string(), unescaped))
+}
+
+/**
+ * Move symbols may be glued to their move number, as in "1.e4" or "12...Nf6"
+ */
+fn strip_move_number(symbol: &str) -> &str {
+    let digits = symbol.trim_start_matches(|c: char| c.is_ascii_digit());
+    if digits.len() < symbol.len() && digits.starts_with('.') {
+        digits.trim_start_matches('.')
+    } else {
+        symbol
+    }
+}
+
+/**
+ * Move suffix annotations written apart from their move, as in "e4 !?"
+ */
+fn is_annotation(symbol: &str) -> bool {
+    ["!", "?", "!!", "??", "!?", "?!"].contains(&symbol)
+}
+
+/**
+ * Reads every game of a PGN file
+ */
+pub fn parse_games(text: &str) -> Result<Vec<PgnGame>, PgnError> {
+    let mut games = Vec::new();
+    let mut game: Option<PgnGame> = None;
+    let mut variation_depth = 0;
+
+    for token in tokenize(text)? {
+        if variation_depth > 0 {
+            match token {
+                Token::VariationStart => variation_depth += 1,
+                Token::VariationEnd => variation_depth -= 1,
+                _ => (),
+            }
+            continue;
+        }
+        match token {
+            Token::Tag(name, value) => {
+                if let Some(previous) = game.take_if(|current| !current.moves.is_empty()) {
+                    games.push(previous);
+                }
+                game.get_or_insert_with(PgnGame::untagged)
+                    .tags
+                    .push((name, value));
+            }
+            Token::Comment(comment) => {
+                if let Some(last) = game.as_mut().and_then(|current| current.moves.last_mut()) {
+                    last.comment = Some(match last.comment.take() {
+                        Some(previous) => previous + " " + &comment,
+                        None => comment,
+                    });
+                }
+            }
+            Token::VariationStart => variation_depth += 1,
+            Token::VariationEnd => {
+                return Err(PgnError::Syntax("unmatched ')'".to_string()));
+            }
+            Token::Nag => (),
+            Token::Symbol(symbol) => {
+                let current = game.get_or_insert_with(PgnGame::untagged);
+                if RESULTS.contains(&symbol.as_str()) {
+                    current.result = symbol;
+                    games.extend(game.take());
+                    continue;
+                }
+                let san = strip_move_number(&symbol);
+                if !san.is_empty() && !is_annotation(san) {
+                    current.moves.push(PgnMove {
+                        san: san.to_string(),
+                        comment: None,
+                    });
+                }
+            }
+        }
+    }
+    if variation_depth > 0 {
+        return Err(PgnError::Syntax("unterminated variation".to_string()));
+    }
+    games.extend(game);
+    Ok(games)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_games, PgnGame, MAX_LINE_LENGTH, START_FEN};
+    use crate::model::{algebraic_notation, fixtures::board};
+
+    const GAMES: &str = r#"% Exported by hand
+[Event "Casual \"blitz\" [rated]"]
+[Site "?"]
+[Result "1-0"]
+
+1. e4 e5 $1 2. Nf3 (2. f4 exf4 (2... d5) 3. Nf3) 2... Nc6 {Both knights out} 3. Bc4 !?
+Nf6?! 4. Ng5 d5 5. exd5 Na5 ; Polerio defence
+6. Bb5+ c6 1-0
+
+[Event "Second"]
+[SetUp "1"]
+[FEN "4k3/8/8/8/8/8/4P3/4K3 w - - 0 30"]
+[Result "*"]
+
+30.e4 {[%clk 0:01:00]} 30...Kd7 31. Kd2 *
+"#;
+
+    fn round_trip(game: &PgnGame) -> PgnGame {
+        let mut games = parse_games(&game.to_string()).unwrap();
+        assert_eq!(games.len(), 1);
+        games.remove(0)
+    }
+
+    #[test]
+    fn reads_games_with_variations_and_annotations() {
+        let games = parse_games(GAMES).unwrap();
+        assert_eq!(games.len(), 2);
+
+        let first = &games[0];
+        assert_eq!(first.tag("Event"), Some("Casual \"blitz\" [rated]"));
+        assert_eq!(first.result, "1-0");
+        let sans: Vec<&str> = first.moves.iter().map(|m| m.san.as_str()).collect();
+        assert_eq!(
+            sans,
+            ["e4", "e5", "Nf3", "Nc6", "Bc4", "Nf6?!", "Ng5", "d5", "exd5", "Na5", "Bb5+", "c6"]
+        );
+        assert_eq!(first.moves[3].comment.as_deref(), Some("Both knights out"));
+        assert_eq!(first.moves[9].comment.as_deref(), Some("Polerio defence"));
+        assert!(first.to_board().is_ok());
+
+        let second = &games[1];
+        assert_eq!(second.moves.len(), 3);
+        assert_eq!(second.moves[0].comment.as_deref(), Some("[%clk 0:01:00]"));
+        assert_eq!(
+            second.to_board().unwrap().to_fen(),
+            "8/3k4/8/8/4P3/8/3K4/8 b - - 2 31"
+        );
+
+        for game in games.iter() {
+            assert_eq!(&round_trip(game), game);
+        }
+    }
+
+    #[test]
+    fn writes_games_from_a_position() {
+        let fen = "r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 3 20";
+        let mut board = board(fen);
+        for san in ["O-O-O", "O-O", "Rd2"] {
+            let action = algebraic_notation::from_algebraic_notation(&board, san).unwrap();
+            board.do_move(action).unwrap();
+        }
+        let game = PgnGame::from_board(&mut board);
+        assert_eq!(game.tag("SetUp"), Some("1"));
+        assert_eq!(game.tag("FEN"), Some(fen));
+        assert!(game.to_string().contains("20... O-O-O 21. O-O Rd2 *"));
+
+        let read = round_trip(&game);
+        assert_eq!(read, game);
+        assert_eq!(read.to_board().unwrap().to_fen(), board.to_fen());
+    }
+
+    #[test]
+    fn wraps_long_movetexts() {
+        let mut board = board(START_FEN);
+        for _ in 0..12 {
+            for san in ["Nf3", "Nf6", "Ng1", "Ng8"] {
+                let action = algebraic_notation::from_algebraic_notation(&board, san).unwrap();
+                board.do_move(action).unwrap();
+            }
+        }
+        let mut game = PgnGame::from_board(&mut board);
+        game.set_clocks(&[std::time::Duration::from_secs(60); 48]);
+        let text = game.to_string();
+        assert!(text.lines().count() > 10);
+        assert!(text.lines().all(|line| line.len() <= MAX_LINE_LENGTH));
+        assert_eq!(round_trip(&game), game);
+    }
+}
@@ -1,14 +1,11 @@
 use std::collections::HashMap;
-use std::ops::Add;
 
 use super::actions;
 use super::actions::BoardPins;
-use super::actions::MovesList;
-use super::actions::PinState;
+use super::actions::{Action, ActionKind, MovesList};
 use super::board::Board;
 use super::board::Square;
 use super::board::BOARD_X;
-use super::chess_actions::movement::Move;
 
 pub const KNIGHT_OFFSETS: [i32; 8] = [-21, -19, -12, -8, 8, 12, 19, 21];
 pub const DIRECTIONS: [i32; 8] = [-10, -1, 1, 10, -11, -9, 9, 11];
@@ -34,133 +31,147 @@ pub enum Piece {
     Pawn { color: Color },
     Bishop { color: Color },
     Knight { color: Color },
-    Rook { color: Color, first_move: u32 }, // Turn where the Rook first moved
+    Rook { color: Color },
     Queen { color: Color },
-    King { color: Color, first_move: u32 }, // Turn where the King first moved
+    King { color: Color },
 }
 
 fn pawn_moves(
+    moves: &mut MovesList,
     position: usize,
     color: &Color,
     board: &Board,
-    resolve_check: &Vec<usize>,
+    resolve_check: &[usize],
     pins: &BoardPins,
-) -> MovesList {
-    let mut moves = MovesList(Vec::new());
+) {
     let direction: i32 = match color {
         Color::WHITE => -1,
         Color::BLACK => 1,
     } * BOARD_X as i32;
 
+    let pawn = Piece::Pawn { color: *color };
     let right_dir = direction - 1;
     let left_dir = direction + 1;
     let take_right = (position as i32 + (right_dir)) as usize;
     let take_left = (position as i32 + (left_dir)) as usize;
+    // Taking en passant also resolves a check given by the pawn that just moved two squares
+    let resolves_check = |target: usize| {
+        resolve_check.is_empty()
+            || resolve_check.contains(&target)
+            || board
+                .double_pawn_move()
+                .is_some_and(|(ghost, pawn)| ghost == target && resolve_check.contains(&pawn))
+    };
 
-    if (resolve_check.is_empty() || resolve_check.contains(&take_left))
-        && pins.can_move_in_direction(position, left_dir)
-    {
-        moves.extend(actions::pawn_captures(position, take_left, color, board));
+    if resolves_check(take_left) && pins.can_move_in_direction(position, left_dir) {
+        actions::to_promotion(
+            moves,
+            actions::pawn_captures(position, take_left, color, board),
+            &pawn,
+            take_left,
+        );
     }
 
-    if (resolve_check.is_empty() || resolve_check.contains(&take_right))
-        && pins.can_move_in_direction(position, right_dir)
-    {
-        moves.extend(actions::pawn_captures(position, take_right, color, board));
+    if resolves_check(take_right) && pins.can_move_in_direction(position, right_dir) {
+        actions::to_promotion(
+            moves,
+            actions::pawn_captures(position, take_right, color, board),
+            &pawn,
+            take_right,
+        );
     }
 
     if !pins.can_move_in_direction(position, direction) {
-        return moves;
+        return;
     }
 
     let push_one = (position as i32 + direction) as usize;
 
     if let Square::Inside(Some(_)) = board.piece_at_mailbox_index(push_one) {
-        return moves;
+        return;
     };
 
     if resolve_check.is_empty() || resolve_check.contains(&push_one) {
         // Push pawn one square
-        moves.push(Box::new(Move::new(
-            position,
-            (position as i32 + direction) as usize,
-        )));
+        actions::to_promotion(
+            moves,
+            Some(Action::new(position, push_one, ActionKind::Move)),
+            &pawn,
+            push_one,
+        );
     }
 
     let push_two = (position as i32 + 2 * direction) as usize;
 
     // Push 2 squares
-    if Board::is_on_pawn_flag(color, position) && resolve_check.is_empty()
-        || resolve_check.contains(&push_two)
+    if Board::is_on_pawn_flag(color, position)
+        && (resolve_check.is_empty() || resolve_check.contains(&push_two))
     {
         if let Square::Inside(Some(_)) = board.piece_at_mailbox_index(push_two) {
-            return moves;
+            return;
         }
-        moves.push(Box::new(Move::new(position, push_two)));
+        moves.push(Action::new(position, push_two, ActionKind::DoublePush));
     }
-
-    return moves;
 }
 
 fn moves_from_slice(
+    moves: &mut MovesList,
     position: usize,
     directions: &[i32],
     piece: &Piece,
     board: &Board,
-    resolve_check: &Vec<usize>,
+    resolve_check: &[usize],
     pins: &BoardPins,
-) -> MovesList {
-    let mut moves = MovesList(Vec::new());
+) {
     for direction in directions {
-        moves.append(&mut actions::get_moves_for_piece_and_direction(
+        actions::get_moves_for_piece_and_direction(
+            moves,
             position,
             *direction,
-            piece.is_sliding(),
             piece,
             board,
             resolve_check,
             pins,
-        ))
+        )
     }
-    return moves;
 }
 
 impl Piece {
+    /**
+     * Adds the legal moves of the piece to the list
+     */
     pub fn valid_moves(
         &self,
+        moves: &mut MovesList,
         position: usize,
         board: &Board,
-        resolve_check: &Vec<usize>,
+        resolve_check: &[usize],
         pins: &BoardPins,
-    ) -> MovesList {
+    ) {
         use Piece::*;
         if self.get_color() != board.color_turn() {
-            return MovesList(Vec::new());
+            return;
         }
         match self {
-            Pawn { color } => pawn_moves(position, color, board, resolve_check, pins),
-            King {
-                color,
-                first_move: _,
-            } => {
-                let mut moves = MovesList(Vec::new());
+            Pawn { color } => pawn_moves(moves, position, color, board, resolve_check, pins),
+            King { color } => {
                 for direction in DIRECTIONS {
                     if actions::can_king_move(board, color, position, direction) {
-                        moves.append(&mut actions::get_moves_for_piece_and_direction(
+                        actions::get_moves_for_piece_and_direction(
+                            moves,
                             position,
                             direction,
-                            false,
                             self,
                             board,
-                            &vec![],
+                            &[],
                             &BoardPins(HashMap::new()),
-                        ))
+                        )
                     }
                 }
-                moves.append(&mut actions::castles(position, self, board));
-                moves
+                actions::castles(moves, position, self, board);
             }
             _ => moves_from_slice(
+                moves,
                 position,
                 self.get_direction(),
                 self,
@@ -177,9 +188,27 @@ impl Piece {
             Pawn { color } => color,
             Bishop { color } => color,
             Knight { color } => color,
-            Rook { color, .. } => color,
+            Rook { color } => color,
             Queen { color } => color,
-            King { color, .. } => color,
+            King { color } => color,
+        }
+    }
+
+    /**
+     * FEN letter of the piece, uppercase for white and lowercase for black
+     */
+    pub fn to_fen_char(&self) -> char {
+        let c = match self {
+            Piece::Pawn { .. } => 'p',
+            Piece::Bishop { .. } => 'b',
+            Piece::Knight { .. } => 'n',
+            Piece::Rook { .. } => 'r',
+            Piece::Queen { .. } => 'q',
+            Piece::King { .. } => 'k',
+        };
+        match self.get_color() {
+            Color::WHITE => c.to_ascii_uppercase(),
+            Color::BLACK => c,
         }
     }
 
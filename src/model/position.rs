@@ -0,0 +1,75 @@
+use super::{
+    actions::{self, Action, ActionKey, MovesList},
+    board::{Board, InvalidBoardErr},
+    piece::{Color, Piece},
+};
+
+/**
+ * A chess position able to generate, play and undo its legal actions.
+ * Implemented by the 10x12 mailbox Board and by the bitboard BitboardPosition,
+ * so that generate_moves, perft, the bench, the console board view and the game window drawing
+ * work with both. The search and the evaluation still read the mailbox Board
+ */
+pub trait Position: Sized {
+    type Action;
+    type Actions: IntoIterator<Item = Self::Action>;
+
+    fn from_fen(fen: &str) -> Result<Self, InvalidBoardErr>;
+    fn to_fen(&self) -> String;
+    fn side_to_move(&self) -> Color;
+    /**
+     * Piece on a square of the actual board, from 0 (a8) to 63 (h1)
+     */
+    fn piece_at(&self, square: usize) -> Option<Piece>;
+    fn legal_actions(&self) -> Self::Actions;
+    fn action_key(&self, action: &Self::Action) -> ActionKey;
+    fn play(&mut self, action: Self::Action);
+    fn undo(&mut self) -> Option<Self::Action>;
+
+    /**
+     * Legal action written in long algebraic notation, such as e2e4 or e7e8q
+     */
+    fn find_action(&self, notation: &str) -> Option<Self::Action> {
+        self.legal_actions()
+            .into_iter()
+            .find(|action| self.action_key(action).to_string() == notation)
+    }
+}
+
+impl Position for Board {
+    type Action = Action;
+    type Actions = MovesList;
+
+    fn from_fen(fen: &str) -> Result<Self, InvalidBoardErr> {
+        Board::from_fen(fen.to_string())
+    }
+
+    fn to_fen(&self) -> String {
+        Board::to_fen(self)
+    }
+
+    fn side_to_move(&self) -> Color {
+        *self.color_turn()
+    }
+
+    fn piece_at(&self, square: usize) -> Option<Piece> {
+        *self.piece_at_board_index(square)
+    }
+
+    fn legal_actions(&self) -> MovesList {
+        actions::generate_board_moves(self)
+    }
+
+    fn action_key(&self, action: &Self::Action) -> ActionKey {
+        ActionKey::of(action)
+    }
+
+    fn play(&mut self, action: Self::Action) {
+        self.do_move(action)
+            .expect("a legal action could not be played");
+    }
+
+    fn undo(&mut self) -> Option<Self::Action> {
+        self.undo_last_move()
+    }
+}
@@ -0,0 +1,261 @@
+use std::fmt;
+
+use super::{
+    actions,
+    board::{Board, BOARD_X},
+    piece::{Color, Piece},
+};
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum GameStatus {
+    Ongoing,
+    Checkmate { winner: Color },
+    Stalemate,
+    FiftyMoveRule,
+    ThreefoldRepetition,
+    InsufficientMaterial,
+    Timeout { winner: Color },
+    TimeoutVsInsufficientMaterial, // The flag fell but the opponent could not have mated
+}
+
+impl GameStatus {
+    pub fn is_over(&self) -> bool {
+        *self != GameStatus::Ongoing
+    }
+
+    /**
+     * Result as written in PGN files
+     */
+    pub fn result(&self) -> &'static str {
+        match self {
+            GameStatus::Ongoing => "*",
+            GameStatus::Checkmate {
+                winner: Color::WHITE,
+            }
+            | GameStatus::Timeout {
+                winner: Color::WHITE,
+            } => "1-0",
+            GameStatus::Checkmate {
+                winner: Color::BLACK,
+            }
+            | GameStatus::Timeout {
+                winner: Color::BLACK,
+            } => "0-1",
+            _ => "1/2-1/2",
+        }
+    }
+}
+
+impl fmt::Display for GameStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameStatus::Ongoing => write!(f, "Game in progress"),
+            GameStatus::Checkmate {
+                winner: Color::WHITE,
+            } => write!(f, "Checkmate, white wins"),
+            GameStatus::Checkmate {
+                winner: Color::BLACK,
+            } => write!(f, "Checkmate, black wins"),
+            GameStatus::Stalemate => write!(f, "Draw by stalemate"),
+            GameStatus::FiftyMoveRule => write!(f, "Draw by the fifty-move rule"),
+            GameStatus::ThreefoldRepetition => write!(f, "Draw by threefold repetition"),
+            GameStatus::InsufficientMaterial => write!(f, "Draw by insufficient material"),
+            GameStatus::Timeout {
+                winner: Color::WHITE,
+            } => write!(f, "Black lost on time, white wins"),
+            GameStatus::Timeout {
+                winner: Color::BLACK,
+            } => write!(f, "White lost on time, black wins"),
+            GameStatus::TimeoutVsInsufficientMaterial => {
+                write!(f, "Draw, time out against insufficient material")
+            }
+        }
+    }
+}
+
+impl Board {
+    pub fn status(&self) -> GameStatus {
+        if actions::generate_moves(self).is_empty() {
+            return if actions::is_in_check(self) {
+                GameStatus::Checkmate {
+                    winner: self.color_turn().next(),
+                }
+            } else {
+                GameStatus::Stalemate
+            };
+        }
+        if self.has_insufficient_material() {
+            GameStatus::InsufficientMaterial
+        } else if self.halfmove_clock() >= 100 {
+            GameStatus::FiftyMoveRule
+        } else if self.repetitions() >= 3 {
+            GameStatus::ThreefoldRepetition
+        } else {
+            GameStatus::Ongoing
+        }
+    }
+
+    /**
+     * Status once the flag of the given side fell, a draw when the opponent only has its king and
+     * at most one minor piece
+     */
+    pub fn timeout_status(&self, flagged: &Color) -> GameStatus {
+        let winner = flagged.next();
+        let mut minor_pieces = 0;
+        for (_, piece) in self.pieces_iter() {
+            match piece {
+                piece if *piece.get_color() != winner => (),
+                Piece::King { .. } => (),
+                Piece::Knight { .. } | Piece::Bishop { .. } => minor_pieces += 1,
+                _ => return GameStatus::Timeout { winner },
+            }
+        }
+        if minor_pieces <= 1 {
+            GameStatus::TimeoutVsInsufficientMaterial
+        } else {
+            GameStatus::Timeout { winner }
+        }
+    }
+
+    /**
+     * No sequence of legal moves can lead to a mate : kings with at most one minor piece,
+     * or kings with bishops all standing on squares of the same color
+     */
+    pub fn has_insufficient_material(&self) -> bool {
+        let mut minor_pieces = 0;
+        let mut bishop_squares = Vec::new();
+        for (index, piece) in self.pieces_iter() {
+            match piece {
+                Piece::King { .. } => (),
+                Piece::Knight { .. } => minor_pieces += 1,
+                Piece::Bishop { .. } => {
+                    minor_pieces += 1;
+                    bishop_squares.push((index % BOARD_X + index / BOARD_X) % 2);
+                }
+                _ => return false,
+            }
+        }
+        minor_pieces <= 1
+            || (bishop_squares.len() == minor_pieces
+                && bishop_squares
+                    .iter()
+                    .all(|square| *square == bishop_squares[0]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GameStatus;
+    use crate::model::{
+        board::START_FEN,
+        fixtures::{board, play},
+        piece::Color,
+    };
+
+    #[test]
+    fn ends_on_checkmate_and_stalemate() {
+        assert_eq!(
+            board("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").status(),
+            GameStatus::Ongoing
+        );
+        assert_eq!(
+            board("R5k1/5ppp/8/8/8/8/8/6K1 b - - 1 1").status(),
+            GameStatus::Checkmate {
+                winner: Color::WHITE
+            }
+        );
+        assert_eq!(
+            board("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3").status(),
+            GameStatus::Checkmate {
+                winner: Color::BLACK
+            }
+        );
+        assert_eq!(
+            board("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1").status(),
+            GameStatus::Stalemate
+        );
+    }
+
+    #[test]
+    fn draws_after_fifty_moves() {
+        let fen = "4k3/8/8/8/8/8/4P3/R3K3 w - - 99 80";
+        assert_eq!(board(fen).status(), GameStatus::Ongoing);
+
+        let mut quiet = board(fen);
+        play(&mut quiet, "a1a2");
+        assert_eq!(quiet.status(), GameStatus::FiftyMoveRule);
+
+        // A pawn move starts the count again
+        let mut pawn = board(fen);
+        play(&mut pawn, "e2e4");
+        assert_eq!(pawn.status(), GameStatus::Ongoing);
+
+        // A mate on the hundredth half move still wins
+        assert_eq!(
+            board("R5k1/5ppp/8/8/8/8/8/6K1 b - - 100 80").status(),
+            GameStatus::Checkmate {
+                winner: Color::WHITE
+            }
+        );
+    }
+
+    #[test]
+    fn draws_on_threefold_repetition() {
+        let mut board = board(START_FEN);
+        for _ in 0..2 {
+            for notation in ["g1f3", "g8f6", "f3g1", "f6g8"] {
+                assert_eq!(board.status(), GameStatus::Ongoing);
+                play(&mut board, notation);
+            }
+        }
+        assert_eq!(board.status(), GameStatus::ThreefoldRepetition);
+
+        board.undo_last_move();
+        assert_eq!(board.status(), GameStatus::Ongoing);
+        play(&mut board, "f6g8");
+        assert_eq!(board.status(), GameStatus::ThreefoldRepetition);
+    }
+
+    #[test]
+    fn draws_without_mating_material() {
+        for fen in [
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/2N1K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/2B1K3 w - - 0 1",
+            "4kb2/8/8/8/8/8/8/2B1K3 w - - 0 1",
+            "4k3/8/8/8/8/4B3/8/2B1K3 b - - 0 1",
+        ] {
+            assert_eq!(
+                board(fen).status(),
+                GameStatus::InsufficientMaterial,
+                "{}",
+                fen
+            );
+        }
+        for fen in [
+            "2b1k3/8/8/8/8/8/8/2B1K3 w - - 0 1",
+            "4kn2/8/8/8/8/8/8/2B1K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/1NN1K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/2B1KB2 b - - 0 1",
+            "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/R3K3 w - - 0 1",
+        ] {
+            assert_eq!(board(fen).status(), GameStatus::Ongoing, "{}", fen);
+        }
+    }
+
+    #[test]
+    fn draws_when_the_opponent_cannot_mate() {
+        let board = board("8/8/4k3/8/8/2N5/4K3/7p w - - 0 1");
+        assert_eq!(
+            board.timeout_status(&Color::BLACK),
+            GameStatus::TimeoutVsInsufficientMaterial
+        );
+        assert_eq!(
+            board.timeout_status(&Color::WHITE),
+            GameStatus::Timeout {
+                winner: Color::BLACK
+            }
+        );
+    }
+}
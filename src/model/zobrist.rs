@@ -0,0 +1,93 @@
+use std::sync::OnceLock;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use super::{
+    board::{CastleRights, TO_BOARD},
+    piece::{Color, Piece},
+};
+
+/**
+ * Fixed seed, so that hashes are the same from one run to another
+ */
+const SEED: u64 = 0x5EED_C0FF_EE00_CAFE;
+
+/**
+ * Random keys xored together to identify a position
+ */
+struct ZobristKeys {
+    pieces: [[u64; 64]; 12],
+    castles: [u64; 4],
+    en_passant: [u64; 8],
+    black_to_play: u64,
+}
+
+fn keys() -> &'static ZobristKeys {
+    static KEYS: OnceLock<ZobristKeys> = OnceLock::new();
+    KEYS.get_or_init(|| {
+        let mut rng = StdRng::seed_from_u64(SEED);
+        let mut pieces = [[0; 64]; 12];
+        for squares in pieces.iter_mut() {
+            for key in squares.iter_mut() {
+                *key = rng.gen();
+            }
+        }
+        ZobristKeys {
+            pieces,
+            castles: rng.gen(),
+            en_passant: rng.gen(),
+            black_to_play: rng.gen(),
+        }
+    })
+}
+
+fn piece_index(piece: &Piece) -> usize {
+    let (kind, color) = match piece {
+        Piece::Pawn { color } => (0, color),
+        Piece::Bishop { color } => (1, color),
+        Piece::Knight { color } => (2, color),
+        Piece::Rook { color } => (3, color),
+        Piece::Queen { color } => (4, color),
+        Piece::King { color } => (5, color),
+    };
+    match color {
+        Color::WHITE => kind,
+        Color::BLACK => kind + 6,
+    }
+}
+
+/**
+ * Key of a piece on a mailbox square
+ */
+pub fn piece_key(piece: &Piece, position: usize) -> u64 {
+    keys().pieces[piece_index(piece)][TO_BOARD[position] as usize]
+}
+
+pub fn castle_rights_key(color: &Color, rights: CastleRights) -> u64 {
+    let offset = match color {
+        Color::WHITE => 0,
+        Color::BLACK => 2,
+    };
+    let mut key = 0;
+    if rights.has_king_side() {
+        key ^= keys().castles[offset];
+    }
+    if rights.has_queen_side() {
+        key ^= keys().castles[offset + 1];
+    }
+    key
+}
+
+/**
+ * Key of the file of an en passant mailbox square
+ */
+pub fn en_passant_key(position: usize) -> u64 {
+    keys().en_passant[TO_BOARD[position] as usize % 8]
+}
+
+pub fn color_key(color: &Color) -> u64 {
+    match color {
+        Color::WHITE => 0,
+        Color::BLACK => keys().black_to_play,
+    }
+}
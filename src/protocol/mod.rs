@@ -0,0 +1,101 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use crate::{
+    generator::search::{self, SearchLimits, SearchResult},
+    model::board::{Board, START_FEN},
+};
+
+pub mod uci;
+pub mod xboard;
+
+pub const ENGINE_NAME: &str = concat!(env!("CARGO_PKG_NAME"), " ", env!("CARGO_PKG_VERSION"));
+/**
+ * Time kept aside to send the move back when playing on a clock
+ */
+const MOVE_OVERHEAD: Duration = Duration::from_millis(50);
+const DEFAULT_MOVES_TO_GO: u32 = 30;
+
+/**
+ * A share of the remaining time on the clock plus half the increment
+ */
+pub fn time_for_move(time: Duration, increment: Duration, moves_to_go: Option<u32>) -> Duration {
+    let moves_to_go = moves_to_go.unwrap_or(DEFAULT_MOVES_TO_GO).max(1);
+    let share = time / moves_to_go + increment / 2;
+    share.min(time.saturating_sub(MOVE_OVERHEAD))
+}
+
+pub fn start_board() -> Board {
+    match Board::from_fen(START_FEN.to_string()) {
+        Ok(board) => board,
+        Err(e) => panic!("Invalid board {}", e.err),
+    }
+}
+
+/**
+ * A search running on its own thread. The board is moved to the thread and given back when it ends,
+ * `on_done` is called from the thread with the final result unless the search is cancelled
+ */
+pub struct BackgroundSearch {
+    stop: Arc<AtomicBool>,
+    cancelled: Arc<AtomicBool>,
+    handle: JoinHandle<Board>,
+}
+
+impl BackgroundSearch {
+    /**
+     * An infinite search keeps waiting once it has reached its limits, until it is stopped
+     */
+    pub fn start<I, D>(
+        mut board: Board,
+        limits: SearchLimits,
+        infinite: bool,
+        on_iteration: I,
+        on_done: D,
+    ) -> Self
+    where
+        I: FnMut(&SearchResult) + Send + 'static,
+        D: FnOnce(&mut Board, &SearchResult) + Send + 'static,
+    {
+        let stop = Arc::new(AtomicBool::new(false));
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let (thread_stop, thread_cancelled) = (stop.clone(), cancelled.clone());
+        let handle = thread::spawn(move || {
+            let result = search::search(&mut board, limits, &thread_stop, on_iteration);
+            while infinite && !thread_stop.load(Ordering::Relaxed) {
+                thread::sleep(Duration::from_millis(5));
+            }
+            if !thread_cancelled.load(Ordering::Relaxed) {
+                on_done(&mut board, &result);
+            }
+            board
+        });
+        BackgroundSearch {
+            stop,
+            cancelled,
+            handle,
+        }
+    }
+
+    /**
+     * Stops the search as soon as possible, keeping its best move so far
+     */
+    pub fn finish(self) -> Board {
+        self.stop.store(true, Ordering::Relaxed);
+        self.handle.join().expect("The search thread panicked")
+    }
+
+    /**
+     * Stops the search and drops its result
+     */
+    pub fn cancel(self) -> Board {
+        self.cancelled.store(true, Ordering::Relaxed);
+        self.finish()
+    }
+}
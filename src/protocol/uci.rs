@@ -0,0 +1,253 @@
+use std::{
+    io::{self, BufRead},
+    time::Duration,
+};
+
+use crate::{
+    generator::search::{SearchLimits, SearchResult},
+    model::{
+        actions::ActionKey,
+        board::{Board, START_FEN},
+        piece::Color,
+    },
+};
+
+use super::{start_board, time_for_move, BackgroundSearch, ENGINE_NAME};
+
+/**
+ * Parameters of the "go" command
+ */
+#[derive(Default, Debug)]
+struct GoParameters {
+    depth: Option<u32>,
+    movetime: Option<Duration>,
+    wtime: Option<Duration>,
+    btime: Option<Duration>,
+    winc: Option<Duration>,
+    binc: Option<Duration>,
+    movestogo: Option<u32>,
+    infinite: bool,
+}
+
+impl GoParameters {
+    fn parse<'a>(mut tokens: impl Iterator<Item = &'a str>) -> Self {
+        let mut parameters = GoParameters::default();
+        while let Some(token) = tokens.next() {
+            match token {
+                "depth" => parameters.depth = tokens.next().and_then(|value| value.parse().ok()),
+                "movetime" => parameters.movetime = tokens.next().and_then(parse_millis),
+                "wtime" => parameters.wtime = tokens.next().and_then(parse_millis),
+                "btime" => parameters.btime = tokens.next().and_then(parse_millis),
+                "winc" => parameters.winc = tokens.next().and_then(parse_millis),
+                "binc" => parameters.binc = tokens.next().and_then(parse_millis),
+                "movestogo" => {
+                    parameters.movestogo = tokens.next().and_then(|value| value.parse().ok())
+                }
+                "infinite" => parameters.infinite = true,
+                _ => (),
+            }
+        }
+        parameters
+    }
+
+    /**
+     * A fixed time per move, or a share of the remaining time on the clock
+     */
+    fn time_for_move(&self, color: &Color) -> Option<Duration> {
+        if self.movetime.is_some() {
+            return self.movetime;
+        }
+        let (time, increment) = match color {
+            Color::WHITE => (self.wtime?, self.winc),
+            Color::BLACK => (self.btime?, self.binc),
+        };
+        Some(time_for_move(
+            time,
+            increment.unwrap_or(Duration::ZERO),
+            self.movestogo,
+        ))
+    }
+
+    fn limits(&self, color: &Color) -> SearchLimits {
+        if self.infinite {
+            return SearchLimits::default();
+        }
+        SearchLimits {
+            depth: self.depth,
+            movetime: self.time_for_move(color),
+        }
+    }
+}
+
+/**
+ * Some GUIs send negative times once the clock ran out
+ */
+fn parse_millis(value: &str) -> Option<Duration> {
+    value
+        .parse::<i64>()
+        .ok()
+        .map(|millis| Duration::from_millis(millis.max(0) as u64))
+}
+
+struct UciEngine {
+    board: Board,
+    search: Option<BackgroundSearch>,
+}
+
+impl UciEngine {
+    fn new() -> Self {
+        UciEngine {
+            board: start_board(),
+            search: None,
+        }
+    }
+
+    /**
+     * Handles a command, returns false when the engine must quit
+     */
+    fn handle(&mut self, line: &str) -> bool {
+        let mut tokens = line.split_whitespace();
+        match tokens.next() {
+            Some("uci") => {
+                println!("id name {}", ENGINE_NAME);
+                println!("uciok");
+            }
+            Some("isready") => println!("readyok"),
+            Some("ucinewgame") => {
+                self.stop_search();
+                self.board = start_board();
+            }
+            Some("position") => {
+                self.stop_search();
+                self.set_position(tokens.collect());
+            }
+            Some("go") => {
+                self.stop_search();
+                self.go(GoParameters::parse(tokens));
+            }
+            Some("stop") => self.stop_search(),
+            Some("quit") => {
+                self.stop_search();
+                return false;
+            }
+            // Unknown commands are ignored, as required by the protocol
+            _ => (),
+        }
+        true
+    }
+
+    /**
+     * position [startpos | fen <fen>] [moves <move> ...]
+     * An invalid position leaves the current one untouched
+     */
+    fn set_position(&mut self, tokens: Vec<&str>) {
+        let moves_index = tokens
+            .iter()
+            .position(|token| *token == "moves")
+            .unwrap_or(tokens.len());
+        let fen = match tokens.first() {
+            Some(&"startpos") => START_FEN.to_string(),
+            Some(&"fen") => tokens[1..moves_index].join(" "),
+            _ => {
+                println!("info string invalid position command");
+                return;
+            }
+        };
+        let mut board = match Board::from_fen(fen) {
+            Ok(board) => board,
+            Err(error) => {
+                println!("info string {}", error);
+                return;
+            }
+        };
+        for notation in tokens.iter().skip(moves_index + 1) {
+            let Some(key) = ActionKey::from_long_algebraic(notation, board.color_turn()) else {
+                println!("info string invalid move {}", notation);
+                return;
+            };
+            if let Err(error) = board.try_play(&key) {
+                println!("info string illegal move {} : {}", notation, error);
+                return;
+            }
+        }
+        self.board = board;
+    }
+
+    fn go(&mut self, parameters: GoParameters) {
+        let limits = parameters.limits(self.board.color_turn());
+        let board = std::mem::replace(&mut self.board, Board::empty());
+        self.search = Some(BackgroundSearch::start(
+            board,
+            limits,
+            parameters.infinite,
+            print_info,
+            |_, result| match result.best_move {
+                Some(best_move) => println!("bestmove {}", best_move),
+                None => println!("bestmove 0000"),
+            },
+        ));
+    }
+
+    /**
+     * The search always answers with its best move, even when stopped
+     */
+    fn stop_search(&mut self) {
+        if let Some(search) = self.search.take() {
+            self.board = search.finish();
+        }
+    }
+}
+
+fn print_info(result: &SearchResult) {
+    let score = match result.mate_in() {
+        Some(moves) => format!("mate {}", moves),
+        None => format!("cp {}", result.score),
+    };
+    let millis = result.elapsed.as_millis();
+    let pv: Vec<String> = result.pv.iter().map(|key| key.to_string()).collect();
+    println!(
+        "info depth {} score {} nodes {} nps {} time {} pv {}",
+        result.depth,
+        score,
+        result.nodes,
+        result.nodes as u128 * 1000 / millis.max(1),
+        millis,
+        pv.join(" ")
+    );
+}
+
+/**
+ * Speaks the Universal Chess Interface on the standard input and output until "quit"
+ */
+pub fn run() {
+    let mut engine = UciEngine::new();
+    for line in io::stdin().lock().lines() {
+        let Ok(line) = line else { break };
+        if !engine.handle(&line) {
+            return;
+        }
+    }
+    engine.stop_search();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{UciEngine, START_FEN};
+
+    #[test]
+    fn keeps_the_position_on_an_invalid_fen() {
+        let mut engine = UciEngine::new();
+        assert!(engine.handle("position startpos moves e2e4"));
+        let fen = engine.board.to_fen();
+        for command in [
+            "position fen 4k3/8/8/8/8/8/8/4K3p w - - 0 1",
+            "position fen 4k3/8/4K3 w - - 0 1",
+            "position fen 4k3/8/8/8/8/8/8/4K3 w - - 0 1 moves e1e3",
+        ] {
+            assert!(engine.handle(command));
+            assert_eq!(engine.board.to_fen(), fen);
+        }
+        assert!(engine.handle("position startpos"));
+        assert_eq!(engine.board.to_fen(), START_FEN);
+    }
+}
@@ -0,0 +1,343 @@
+use std::{
+    io::{self, BufRead},
+    time::Duration,
+};
+
+use crate::{
+    generator::search::{SearchLimits, SearchResult},
+    model::{
+        actions::{self, ActionKey},
+        board::Board,
+        piece::Color,
+    },
+};
+
+use super::{start_board, time_for_move, BackgroundSearch, ENGINE_NAME};
+
+/**
+ * Time controls set by "level" and "st", the default is the one of XBoard : 40 moves in 5 minutes
+ */
+#[derive(Clone, Copy, Debug)]
+enum TimeControl {
+    Conventional {
+        moves: u32, // 0 when the base time is for the whole game
+        base: Duration,
+        increment: Duration,
+    },
+    FixedPerMove(Duration),
+}
+
+struct XboardEngine {
+    board: Board,
+    search: Option<BackgroundSearch>,
+    force: bool,
+    engine_color: Color,
+    post: bool,
+    depth: Option<u32>,
+    time_control: TimeControl,
+    engine_time: Option<Duration>,
+}
+
+impl XboardEngine {
+    fn new() -> Self {
+        XboardEngine {
+            board: start_board(),
+            search: None,
+            force: false,
+            engine_color: Color::BLACK,
+            post: false,
+            depth: None,
+            time_control: TimeControl::Conventional {
+                moves: 40,
+                base: Duration::from_secs(5 * 60),
+                increment: Duration::ZERO,
+            },
+            engine_time: None,
+        }
+    }
+
+    /**
+     * Handles a command, returns false when the engine must quit
+     */
+    fn handle(&mut self, line: &str) -> bool {
+        let mut tokens = line.split_whitespace();
+        let Some(command) = tokens.next() else {
+            return true;
+        };
+        match command {
+            "xboard" | "accepted" | "rejected" | "random" | "hard" | "easy" | "computer"
+            | "name" | "rating" | "ics" | "otim" | "draw" => (),
+            "protover" => println!(
+                "feature myname=\"{}\" ping=1 setboard=1 usermove=1 san=0 time=1 draw=0 \
+                 sigint=0 sigterm=0 reuse=1 analyze=0 colors=0 done=1",
+                ENGINE_NAME
+            ),
+            "new" => {
+                self.cancel_search();
+                self.board = start_board();
+                self.force = false;
+                self.engine_color = Color::BLACK;
+                self.depth = None;
+                self.engine_time = None;
+            }
+            "force" | "result" => {
+                self.cancel_search();
+                self.force = true;
+            }
+            "go" => {
+                self.cancel_search();
+                self.force = false;
+                self.engine_color = *self.board.color_turn();
+                self.think();
+            }
+            "?" => {
+                if let Some(search) = self.search.take() {
+                    self.board = search.finish();
+                }
+            }
+            "usermove" => match tokens.next() {
+                Some(notation) => self.user_move(notation),
+                None => println!("Error (missing move): usermove"),
+            },
+            "undo" => {
+                self.cancel_search();
+                self.board.undo_last_move();
+            }
+            "remove" => {
+                self.cancel_search();
+                self.board.undo_last_move();
+                self.board.undo_last_move();
+            }
+            "setboard" => {
+                self.cancel_search();
+                match Board::from_fen(tokens.collect::<Vec<&str>>().join(" ")) {
+                    Ok(board) => self.board = board,
+                    Err(_) => println!("tellusererror Illegal position"),
+                }
+            }
+            "level" => match parse_level(tokens.collect()) {
+                Some(time_control) => self.time_control = time_control,
+                None => println!("Error (invalid time control): {}", line),
+            },
+            "st" => match tokens
+                .next()
+                .and_then(|seconds| seconds.parse::<f64>().ok())
+            {
+                Some(seconds) if seconds > 0.0 => {
+                    self.time_control = TimeControl::FixedPerMove(Duration::from_secs_f64(seconds))
+                }
+                _ => println!("Error (invalid time per move): {}", line),
+            },
+            "sd" => match tokens.next().and_then(|depth| depth.parse().ok()) {
+                Some(depth) => self.depth = Some(depth),
+                None => println!("Error (invalid depth): {}", line),
+            },
+            "time" => {
+                // Centiseconds left on the engine clock
+                self.engine_time = tokens
+                    .next()
+                    .and_then(|time| time.parse::<i64>().ok())
+                    .map(|centiseconds| Duration::from_millis(centiseconds.max(0) as u64 * 10))
+            }
+            "ping" => println!("pong {}", tokens.next().unwrap_or("")),
+            "post" => self.post = true,
+            "nopost" => self.post = false,
+            "quit" => {
+                self.cancel_search();
+                return false;
+            }
+            // Moves may still be sent without the "usermove" prefix
+            notation if ActionKey::from_long_algebraic(notation, &Color::WHITE).is_some() => {
+                self.user_move(notation)
+            }
+            _ => println!("Error (unknown command): {}", command),
+        }
+        true
+    }
+
+    fn user_move(&mut self, notation: &str) {
+        if let Err(reply) = self.play_user_move(notation) {
+            println!("{}", reply);
+            return;
+        }
+        if !report_result(&self.board)
+            && !self.force
+            && *self.board.color_turn() == self.engine_color
+        {
+            self.think();
+        }
+    }
+
+    /**
+     * Plays the move sent by the user, or gives back the reply refusing it
+     */
+    fn play_user_move(&mut self, notation: &str) -> Result<(), String> {
+        self.cancel_search();
+        let key = ActionKey::from_long_algebraic(notation, self.board.color_turn())
+            .ok_or(format!("Illegal move: {}", notation))?;
+        self.board
+            .try_play(&key)
+            .map_err(|error| format!("Illegal move ({}): {}", error, notation))?;
+        Ok(())
+    }
+
+    fn limits(&self) -> SearchLimits {
+        let movetime = match self.time_control {
+            TimeControl::FixedPerMove(time) => time,
+            TimeControl::Conventional {
+                moves,
+                base,
+                increment,
+            } => {
+                let played = (self.board.turn() - 1) / 2;
+                let moves_to_go = (moves > 0).then(|| moves - played % moves);
+                time_for_move(self.engine_time.unwrap_or(base), increment, moves_to_go)
+            }
+        };
+        SearchLimits {
+            depth: self.depth,
+            movetime: Some(movetime),
+        }
+    }
+
+    /**
+     * Searches in the background, then plays and sends the best move
+     */
+    fn think(&mut self) {
+        if report_result(&self.board) {
+            return;
+        }
+        let limits = self.limits();
+        let post = self.post;
+        let board = std::mem::replace(&mut self.board, Board::empty());
+        self.search = Some(BackgroundSearch::start(
+            board,
+            limits,
+            false,
+            move |result| {
+                if post {
+                    print_thinking(result)
+                }
+            },
+            |board, result| {
+                let action = result
+                    .best_move
+                    .and_then(|key| actions::generate_moves(board).take(&key));
+                if let Some(action) = action.filter(|action| board.do_move(*action).is_ok()) {
+                    println!("move {}", ActionKey::of(&action));
+                    report_result(board);
+                }
+            },
+        ));
+    }
+
+    /**
+     * A move already sent by the search stays played
+     */
+    fn cancel_search(&mut self) {
+        if let Some(search) = self.search.take() {
+            self.board = search.cancel();
+        }
+    }
+}
+
+/**
+ * level MPS BASE INC, the base time is in minutes or minutes:seconds and the increment in seconds
+ */
+fn parse_level(tokens: Vec<&str>) -> Option<TimeControl> {
+    let [moves, base, increment] = tokens.as_slice() else {
+        return None;
+    };
+    let base = match base.split_once(':') {
+        Some((minutes, seconds)) => {
+            Duration::from_secs(minutes.parse::<u64>().ok()? * 60 + seconds.parse::<u64>().ok()?)
+        }
+        None => Duration::from_secs(base.parse::<u64>().ok()? * 60),
+    };
+    Some(TimeControl::Conventional {
+        moves: moves.parse().ok()?,
+        base,
+        increment: Duration::from_secs_f64(increment.parse::<f64>().ok()?.max(0.0)),
+    })
+}
+
+/**
+ * Sends the result when the game is over, returns whether it is
+ */
+fn report_result(board: &Board) -> bool {
+    let status = board.status();
+    if status.is_over() {
+        println!("{} {{{}}}", status.result(), status);
+    }
+    status.is_over()
+}
+
+/**
+ * Thinking output : ply, score, time in centiseconds, nodes and principal variation
+ */
+fn print_thinking(result: &SearchResult) {
+    let pv: Vec<String> = result.pv.iter().map(|key| key.to_string()).collect();
+    println!(
+        "{} {} {} {} {}",
+        result.depth,
+        result.score,
+        result.elapsed.as_millis() / 10,
+        result.nodes,
+        pv.join(" ")
+    );
+}
+
+/**
+ * Speaks the Chess Engine Communication Protocol (XBoard / WinBoard) on the standard input
+ * and output until "quit"
+ */
+pub fn run() {
+    let mut engine = XboardEngine::new();
+    for line in io::stdin().lock().lines() {
+        let Ok(line) = line else { break };
+        if !engine.handle(&line) {
+            return;
+        }
+    }
+    engine.cancel_search();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::XboardEngine;
+
+    const FEN: &str = "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1";
+
+    #[test]
+    fn plays_and_takes_back_moves_in_force_mode() {
+        let mut engine = XboardEngine::new();
+        for command in ["new", "force", &format!("setboard {}", FEN)] {
+            assert!(engine.handle(command));
+        }
+        assert_eq!(engine.board.to_fen(), FEN);
+
+        assert!(engine.handle("usermove e2e4"));
+        assert!(engine.handle("usermove e8d7"));
+        assert!(engine.handle("e1d2"));
+        assert_eq!(engine.board.to_fen(), "8/3k4/8/8/4P3/8/3K4/8 b - - 2 2");
+
+        assert!(engine.handle("undo"));
+        assert_eq!(engine.board.to_fen(), "8/3k4/8/8/4P3/8/8/4K3 w - - 1 2");
+        assert!(engine.handle("remove"));
+        assert_eq!(engine.board.to_fen(), FEN);
+        assert!(engine.search.is_none());
+    }
+
+    #[test]
+    fn refuses_illegal_moves() {
+        let mut engine = XboardEngine::new();
+        assert!(engine.handle("force"));
+        assert!(engine.handle(&format!("setboard {}", FEN)));
+        for notation in ["e2e5", "e8e7", "e1e3", "zz"] {
+            let reply = engine.play_user_move(notation).unwrap_err();
+            assert!(reply.starts_with("Illegal move"), "{}", reply);
+            assert!(engine.handle(&format!("usermove {}", notation)));
+            assert_eq!(engine.board.to_fen(), FEN);
+        }
+    }
+}
@@ -1,6 +1,8 @@
-use crate::model::board::{Square::*, TO_BOARD, TO_MAILBOX};
+use crate::model::board::Square::*;
 use crate::model::board::{BOARD_SIZE, BOARD_X};
-use crate::model::{board::Board, piece::Color, piece::Piece};
+use crate::model::{
+    bitboard::BitboardPosition, board::Board, piece::Color, piece::Piece, position::Position,
+};
 
 use colored::ColoredString;
 use colored::Colorize;
@@ -40,13 +42,13 @@ impl fmt::Display for Board {
                         None => " ".to_string(),
                     };
 
-                    cells[index] = if ((index % 2 + (index / BOARD_X % 2)) % 2) == 0 {
+                    cells[index] = if (index % 2 + (index / BOARD_X % 2)).is_multiple_of(2) {
                         colored_cell.to_string().on_truecolor(120, 80, 0)
                     } else {
                         colored_cell.to_string().on_truecolor(153, 102, 0)
                     };
 
-                    if let Some((ghost, _)) = self.double_pawn_move {
+                    if let Some((ghost, _)) = self.double_pawn_move() {
                         if ghost == index {
                             cells[index] = colored_cell.to_string().on_truecolor(100, 200, 100);
                         }
@@ -65,6 +67,84 @@ impl fmt::Display for Board {
                 Err(error) => return Err(error),
             }
         }
+
+        let status = self.status();
+        if status.is_over() {
+            writeln!(f)?;
+            write!(f, "{}", status.to_string().bold())?;
+        }
+        Ok(())
+    }
+}
+
+/**
+ * Draws any position from its squares, with the side to move below the board
+ */
+pub fn draw<P: Position>(position: &P, f: &mut Formatter) -> Result {
+    for square in 0..64 {
+        if square % 8 == 0 {
+            writeln!(f)?;
+        }
+        let cell = match position.piece_at(square) {
+            Some(piece) => piece.to_string(),
+            None => " ".to_string(),
+        };
+        let cell = if (square % 8 + square / 8) % 2 == 0 {
+            cell.on_truecolor(153, 102, 0)
+        } else {
+            cell.on_truecolor(120, 80, 0)
+        };
+        write!(f, "{}", cell)?;
+    }
+    writeln!(f)?;
+    match position.side_to_move() {
+        Color::WHITE => write!(f, "White to play"),
+        Color::BLACK => write!(f, "Black to play"),
+    }
+}
+
+impl fmt::Display for BitboardPosition {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        draw(self, f)
+    }
+}
+
+/**
+ * A position seen from one side, with the ranks and files around it
+ */
+pub struct BoardView<'a, P: Position = Board> {
+    pub board: &'a P,
+    pub flipped: bool,
+}
+
+impl<P: Position> fmt::Display for BoardView<'_, P> {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        let squares: Vec<usize> = if self.flipped {
+            (0..64).rev().collect()
+        } else {
+            (0..64).collect()
+        };
+        for row in squares.chunks(8) {
+            write!(f, "{} ", 8 - row[0] / 8)?;
+            for &square in row {
+                let cell = match self.board.piece_at(square) {
+                    Some(piece) => format!("{} ", piece),
+                    None => "  ".to_string(),
+                };
+                let cell = if (square % 8 + square / 8).is_multiple_of(2) {
+                    cell.on_truecolor(153, 102, 0)
+                } else {
+                    cell.on_truecolor(120, 80, 0)
+                };
+                write!(f, "{}", cell)?;
+            }
+            writeln!(f)?;
+        }
+        let files = if self.flipped { "hgfedcba" } else { "abcdefgh" };
+        write!(f, "  ")?;
+        for file in files.chars() {
+            write!(f, "{} ", file)?;
+        }
         Ok(())
     }
 }
@@ -1,2 +1,4 @@
 pub mod console;
+pub mod repl;
+#[cfg(feature = "gui")]
 pub mod tetra_state;
@@ -0,0 +1,234 @@
+use std::{
+    io::{self, BufRead, Write},
+    sync::atomic::AtomicBool,
+};
+
+use colored::Colorize;
+
+use crate::{
+    generator::search::{self, SearchLimits},
+    model::{
+        actions::{self, ActionKey},
+        algebraic_notation,
+        board::Board,
+        piece::Color,
+    },
+    protocol::start_board,
+};
+
+use super::console::BoardView;
+
+const HELP: &str =
+    "Moves are read in SAN (Nf3, exd5, O-O, e8=Q) or long algebraic notation (g1f3)\n\
+     Commands :\n  \
+     undo                      takes back the last move\n  \
+     moves                     lists the legal moves\n  \
+     fen [<fen>]               prints the position, or sets it\n  \
+     flip                      turns the board around\n  \
+     new                       starts a new game\n  \
+     go                        lets the engine play the side to move\n  \
+     engine <white|black|off>  chooses the side played by the engine\n  \
+     help                      prints this message\n  \
+     quit";
+
+/**
+ * A game played in the terminal, the engine answers when it plays the side to move
+ */
+struct ConsoleGame {
+    board: Board,
+    flipped: bool,
+    engine: Option<Color>,
+    limits: SearchLimits,
+}
+
+impl ConsoleGame {
+    fn new(engine: Option<Color>, limits: SearchLimits) -> Self {
+        ConsoleGame {
+            board: start_board(),
+            // The side of the player is at the bottom
+            flipped: engine == Some(Color::WHITE),
+            engine,
+            limits,
+        }
+    }
+
+    fn show(&self) {
+        println!(
+            "{}",
+            BoardView {
+                board: &self.board,
+                flipped: self.flipped,
+            }
+        );
+        let status = self.board.status();
+        if status.is_over() {
+            println!("{}", status.to_string().bold());
+        } else {
+            match self.board.color_turn() {
+                Color::WHITE => println!("White to play"),
+                Color::BLACK => println!("Black to play"),
+            }
+        }
+    }
+
+    /**
+     * Handles a command or a move, returns false when the player quits
+     */
+    fn handle(&mut self, line: &str) -> bool {
+        let mut tokens = line.split_whitespace();
+        let Some(command) = tokens.next() else {
+            return true;
+        };
+        match command {
+            "quit" | "exit" => return false,
+            "help" => println!("{}", HELP),
+            "new" => {
+                self.board = start_board();
+                self.engine_reply();
+                self.show();
+            }
+            "undo" => {
+                if self.board.undo_last_move().is_none() {
+                    println!("No move to undo");
+                } else if self.engine == Some(*self.board.color_turn()) {
+                    // Takes back the engine reply with the move of the player
+                    self.board.undo_last_move();
+                }
+                self.show();
+            }
+            "fen" => {
+                let fen = tokens.collect::<Vec<&str>>().join(" ");
+                if fen.is_empty() {
+                    println!("{}", self.board.to_fen());
+                    return true;
+                }
+                match Board::from_fen(fen) {
+                    Ok(board) => {
+                        self.board = board;
+                        self.engine_reply();
+                        self.show();
+                    }
+                    Err(error) => println!("{}", error),
+                }
+            }
+            "moves" => {
+                let moves: Vec<String> = actions::generate_moves(&self.board)
+                    .into_iter()
+                    .map(|action| action.to_algebraic_notation(&mut self.board))
+                    .collect();
+                println!("{}", moves.join(" "));
+            }
+            "flip" => {
+                self.flipped = !self.flipped;
+                self.show();
+            }
+            "go" => {
+                self.think();
+                self.show();
+            }
+            "engine" => {
+                match tokens.next() {
+                    Some("white") => self.engine = Some(Color::WHITE),
+                    Some("black") => self.engine = Some(Color::BLACK),
+                    Some("off") => self.engine = None,
+                    _ => {
+                        println!("Usage : engine <white|black|off>");
+                        return true;
+                    }
+                }
+                self.engine_reply();
+                self.show();
+            }
+            notation => {
+                if self.play(notation) {
+                    self.engine_reply();
+                    self.show();
+                }
+            }
+        }
+        true
+    }
+
+    /**
+     * Plays a move in long algebraic notation or in SAN, returns whether it was legal
+     */
+    fn play(&mut self, notation: &str) -> bool {
+        let key = match ActionKey::from_long_algebraic(notation, self.board.color_turn()) {
+            Some(key) => key,
+            None => match algebraic_notation::from_algebraic_notation(&self.board, notation) {
+                Ok(action) => ActionKey::of(&action),
+                Err(error) => {
+                    println!("{}", error);
+                    return false;
+                }
+            },
+        };
+        match self.board.try_play(&key) {
+            Ok(_) => true,
+            Err(error) => {
+                println!("Illegal move {} : {}", notation, error);
+                false
+            }
+        }
+    }
+
+    fn engine_reply(&mut self) {
+        if self.engine == Some(*self.board.color_turn()) {
+            self.think();
+        }
+    }
+
+    /**
+     * Searches the position and plays the best move
+     */
+    fn think(&mut self) {
+        if self.board.status().is_over() {
+            return;
+        }
+        let stop = AtomicBool::new(false);
+        let result = search::search(&mut self.board, self.limits, &stop, |_| ());
+        let action = result
+            .best_move
+            .and_then(|key| actions::generate_moves(&self.board).take(&key));
+        if let Some(action) = action {
+            let notation = action.to_algebraic_notation(&mut self.board);
+            if let Err(error) = self.board.do_move(action) {
+                println!("Engine move {} : {}", notation, error);
+                return;
+            }
+            match result.mate_in() {
+                Some(mate) => println!("Engine plays {} (mate in {})", notation, mate),
+                None => println!(
+                    "Engine plays {} (depth {}, score {})",
+                    notation, result.depth, result.score
+                ),
+            }
+        }
+    }
+}
+
+/**
+ * Reads moves and commands from the standard input until it is closed or the player quits
+ */
+pub fn run(engine: Option<Color>, limits: SearchLimits) {
+    let mut game = ConsoleGame::new(engine, limits);
+    println!("{}", HELP);
+    game.engine_reply();
+    game.show();
+    let stdin = io::stdin();
+    loop {
+        print!("> ");
+        if io::stdout().flush().is_err() {
+            return;
+        }
+        let mut line = String::new();
+        match stdin.lock().read_line(&mut line) {
+            Ok(0) | Err(_) => return,
+            Ok(_) => {
+                if !game.handle(line.trim()) {
+                    return;
+                }
+            }
+        }
+    }
+}
@@ -1,24 +1,50 @@
 use std::{
-    cmp::{self, min_by},
-    ops::{Deref, DerefMut},
+    cmp,
+    fs::OpenOptions,
+    io::Write,
     path::Path,
-    thread::current,
+    sync::mpsc::{self, Receiver, TryRecvError},
+    time::{Duration, Instant},
 };
 
 use tetra::{
-    graphics::{self, mesh::Mesh, DrawParams, Rectangle, Texture},
+    graphics::{
+        self,
+        mesh::Mesh,
+        text::{Font, Text},
+        DrawParams, Rectangle, Texture,
+    },
     input::{self, Key, MouseButton},
     math::Vec2,
-    window, Context, State, TetraError,
+    Context, State, TetraError,
 };
 
-use crate::model::{
-    actions::{self, ChessAction},
-    board::{Board, Square, TO_BOARD, TO_MAILBOX},
-    piece::{self, Color, Piece},
+use crate::{
+    generator::search::SearchLimits,
+    model::{
+        actions::{self, Action, ActionKey},
+        board::{Board, TO_BOARD, TO_MAILBOX},
+        clock::{Clock, TimeControl},
+        pgn::PgnGame,
+        piece::{self, Color, Piece},
+        position::Position,
+        status::GameStatus,
+    },
+    protocol::{self, BackgroundSearch},
 };
 const PIECE_TO_SQUARE_RATIO: f32 = 0.9;
 pub const SQUARE_SIZE: f32 = 120.0;
+const GAMES_FILE: &str = "./games.pgn";
+const FONT_SIZE: f32 = 32.0;
+const COORDINATE_FONT_SIZE: f32 = 18.0;
+const COORDINATE_MARGIN: f32 = 4.0;
+pub const PANEL_WIDTH: f32 = 360.0;
+const PANEL_FONT_SIZE: f32 = 24.0;
+const PANEL_MARGIN: f32 = 16.0;
+const PANEL_LINE_HEIGHT: f32 = 32.0;
+const PANEL_NUMBER_WIDTH: f32 = 64.0;
+const PANEL_MOVE_WIDTH: f32 = 140.0;
+const CLOCK_HEIGHT: f32 = 56.0;
 
 struct PiecesAsset {
     king: Texture,
@@ -63,10 +89,29 @@ impl PiecesAsset {
     }
 }
 
+/**
+ * The engine searching a copy of the board, its best move is received once the search ends
+ */
+struct EngineSearch {
+    search: BackgroundSearch,
+    best_move: Receiver<Option<ActionKey>>,
+}
+
 pub struct DisplayableBoard {
     pub board: [Option<Piece>; 64],
 }
 
+impl DisplayableBoard {
+    /**
+     * Pieces of any position, square by square from a8 to h1
+     */
+    pub fn from_position<P: Position>(position: &P) -> Self {
+        DisplayableBoard {
+            board: std::array::from_fn(|square| position.piece_at(square)),
+        }
+    }
+}
+
 pub struct TetraState {
     light_square: Texture,
     dark_square: Texture,
@@ -76,10 +121,78 @@ pub struct TetraState {
     pub selected_piece: Option<usize>,
     pub view: DisplayableBoard,
     pub board: Board,
+    pub status: GameStatus,
+    status_text: Text,
+    /**
+     * Promotions of the last dropped pawn, waiting for the player to pick a piece
+     */
+    pub promotion_choices: Vec<Action>,
+    /**
+     * Side played by the engine, None when both sides are played on the board
+     */
+    pub engine: Option<Color>,
+    limits: SearchLimits,
+    thinking: Option<EngineSearch>,
+    /**
+     * Black is at the bottom of the window
+     */
+    pub flipped: bool,
+    /**
+     * Board indexes of the start and target squares of the last action
+     */
+    last_move: Option<(usize, usize)>,
+    /**
+     * Board index of the king of the side to move when it is in check
+     */
+    checked_king: Option<usize>,
+    file_labels: Vec<Text>,
+    rank_labels: Vec<Text>,
+    /**
+     * Actions taken back while browsing the game, the next one last
+     */
+    redo: Vec<Action>,
+    panel_font: Font,
+    /**
+     * SAN of each ply of the game, including the ones taken back
+     */
+    move_texts: Vec<Text>,
+    number_texts: Vec<Text>,
+    /**
+     * Time left to each side at the position shown, None when the game is not timed
+     */
+    clock: Option<Clock>,
+    /**
+     * Time left to the side which played each ply of the game, including the ones taken back
+     */
+    clocks: Vec<Duration>,
+    /**
+     * When the side to move at the last position of the game started thinking
+     */
+    turn_start: Instant,
+    /**
+     * Side whose flag fell, the game is over once the last ply is shown
+     */
+    flagged: Option<Color>,
+    /**
+     * White clock then black clock
+     */
+    clock_texts: Vec<Text>,
 }
 
 impl TetraState {
-    pub fn new(ctx: &mut Context, board: Board) -> tetra::Result<TetraState> {
+    pub fn new(
+        ctx: &mut Context,
+        board: Board,
+        engine: Option<Color>,
+        limits: SearchLimits,
+        flipped: bool,
+        time_control: Option<TimeControl>,
+    ) -> tetra::Result<TetraState> {
+        let coordinates_font = Font::vector(
+            ctx,
+            "./resources/font/DejaVuSansMono.ttf",
+            COORDINATE_FONT_SIZE,
+        )?;
         let mut state = TetraState {
             white_assets: PiecesAsset::load(ctx, Path::new("./resources/white"))?,
             black_assets: PiecesAsset::load(ctx, Path::new("./resources/black"))?,
@@ -91,8 +204,42 @@ impl TetraState {
             view: DisplayableBoard {
                 board: [(); 64].map(|_| None),
             },
+            status: GameStatus::Ongoing,
+            status_text: Text::new(
+                "",
+                Font::vector(ctx, "./resources/font/DejaVuSansMono.ttf", FONT_SIZE)?,
+            ),
+            promotion_choices: Vec::new(),
+            engine,
+            limits,
+            thinking: None,
+            flipped,
+            last_move: None,
+            checked_king: None,
+            file_labels: ('a'..='h')
+                .map(|file| Text::new(file.to_string(), coordinates_font.clone()))
+                .collect(),
+            rank_labels: ('1'..='8')
+                .map(|rank| Text::new(rank.to_string(), coordinates_font.clone()))
+                .collect(),
+            redo: Vec::new(),
+            panel_font: Font::vector(ctx, "./resources/font/DejaVuSansMono.ttf", PANEL_FONT_SIZE)?,
+            move_texts: Vec::new(),
+            number_texts: Vec::new(),
+            clock: time_control.map(Clock::new),
+            clocks: Vec::new(),
+            turn_start: Instant::now(),
+            flagged: None,
+            clock_texts: vec![
+                Text::new(
+                    "",
+                    Font::vector(ctx, "./resources/font/DejaVuSansMono.ttf", FONT_SIZE)?,
+                );
+                2
+            ],
         };
-        state.view = state.board_to_displayable();
+        state.refresh();
+        state.engine_reply();
         Ok(state)
     }
 
@@ -103,31 +250,334 @@ impl TetraState {
         }
     }
 
-    fn x_position(i: usize) -> f32 {
-        SQUARE_SIZE * (i % 8) as f32
+    /**
+     * Square shown at a board index, the board is turned around when flipped. The conversion is its
+     * own inverse, it also gives the board index of a square of the window
+     */
+    fn oriented(&self, i: usize) -> usize {
+        if self.flipped {
+            63 - i
+        } else {
+            i
+        }
+    }
+
+    fn x_position(&self, i: usize) -> f32 {
+        SQUARE_SIZE * (self.oriented(i) % 8) as f32
+    }
+    fn y_position(&self, i: usize) -> f32 {
+        SQUARE_SIZE * (self.oriented(i) / 8) as f32
+    }
+
+    /**
+     * Updates the displayed board and the game status after the board changed
+     */
+    fn refresh(&mut self) {
+        self.view = DisplayableBoard::from_position(&self.board);
+        self.last_move = self.board.history().last().map(|action| {
+            (
+                TO_BOARD[action.start_square()] as usize,
+                TO_BOARD[action.target_square()] as usize,
+            )
+        });
+        let king = self.board.get_king_by_color(self.board.color_turn());
+        self.checked_king = actions::is_in_check(&self.board).then_some(TO_BOARD[king] as usize);
+        let status = match self.flagged {
+            Some(color) if self.redo.is_empty() => self.board.timeout_status(&color),
+            _ => self.board.status(),
+        };
+        if status != self.status && status.is_over() {
+            println!("{}", status);
+        }
+        self.status = status;
+        self.status_text.set_content(status.to_string());
+
+        let game = PgnGame::from_board(&mut self.whole_game());
+        self.move_texts = game
+            .moves
+            .iter()
+            .map(|pgn_move| Text::new(pgn_move.san.as_str(), self.panel_font.clone()))
+            .collect();
+        let first_number = self.first_turn().div_ceil(2) as usize;
+        let rows = (self.move_texts.len() + self.first_slot()).div_ceil(2);
+        self.number_texts = (first_number..first_number + rows)
+            .map(|number| Text::new(format!("{}.", number), self.panel_font.clone()))
+            .collect();
+    }
+
+    /**
+     * The board at the end of the game, with the actions taken back played again
+     */
+    fn whole_game(&self) -> Board {
+        let mut board = self.board.clone();
+        for action in self.redo.iter().rev() {
+            if board.do_move(*action).is_err() {
+                break;
+            }
+        }
+        board
+    }
+
+    /**
+     * Half move of the first action of the game, as counted by the board
+     */
+    fn first_turn(&self) -> u32 {
+        self.board.turn() - self.board.history().len() as u32
+    }
+
+    /**
+     * The black column of the first row holds the first action when black starts
+     */
+    fn first_slot(&self) -> usize {
+        if self.first_turn().is_multiple_of(2) {
+            1
+        } else {
+            0
+        }
+    }
+
+    /**
+     * First row of the panel shown, so that the current action stays in sight
+     */
+    fn first_row(&self) -> usize {
+        let current_row = (self.board.history().len() + self.first_slot()).saturating_sub(1) / 2;
+        (current_row + 1).saturating_sub(self.visible_rows())
+    }
+
+    /**
+     * The move list stands between the clocks when the game is timed
+     */
+    fn moves_top(&self) -> f32 {
+        match self.clock {
+            Some(_) => PANEL_MARGIN + CLOCK_HEIGHT,
+            None => PANEL_MARGIN,
+        }
+    }
+
+    fn visible_rows(&self) -> usize {
+        let height = 8.0 * SQUARE_SIZE - 2.0 * self.moves_top();
+        (height / PANEL_LINE_HEIGHT) as usize
     }
-    fn y_position(i: usize) -> f32 {
-        SQUARE_SIZE * (i / 8) as f32
+
+    /**
+     * Position of the SAN of a ply in the window, None when it is scrolled out
+     */
+    fn ply_position(&self, ply: usize) -> Option<Vec2<f32>> {
+        let slot = ply + self.first_slot();
+        let row = (slot / 2).checked_sub(self.first_row())?;
+        if row >= self.visible_rows() {
+            return None;
+        }
+        Some(Vec2::new(
+            8.0 * SQUARE_SIZE
+                + PANEL_MARGIN
+                + PANEL_NUMBER_WIDTH
+                + PANEL_MOVE_WIDTH * (slot % 2) as f32,
+            self.moves_top() + PANEL_LINE_HEIGHT * row as f32,
+        ))
+    }
+
+    /**
+     * Side which played the given ply of the game
+     */
+    fn mover(&self, ply: usize) -> Color {
+        if (self.first_turn() as usize + ply) % 2 == 1 {
+            Color::WHITE
+        } else {
+            Color::BLACK
+        }
+    }
+
+    /**
+     * The clock runs for the side to move at the last position of the game, until it is over
+     */
+    fn is_clock_running(&self) -> bool {
+        self.clock.is_some() && self.redo.is_empty() && !self.status.is_over()
+    }
+
+    /**
+     * Sets the clock back to the time left at the position shown. The turn keeps its start, so
+     * the side to move at the last position is still charged for the time spent browsing
+     */
+    fn restore_clock(&mut self) {
+        let Some(clock) = &self.clock else {
+            return;
+        };
+        let mut restored = Clock::new(clock.control);
+        for (ply, remaining) in self.clocks.iter().enumerate() {
+            if ply >= self.board.history().len() {
+                break;
+            }
+            restored.set_remaining(&self.mover(ply), *remaining);
+        }
+        self.clock = Some(restored);
+    }
+
+    /**
+     * Ends the game when the side to move ran out of time
+     */
+    fn check_flag(&mut self) {
+        let color = *self.board.color_turn();
+        let flagged = self.is_clock_running()
+            && self
+                .clock
+                .is_some_and(|clock| clock.is_flagged(&color, self.turn_start.elapsed()));
+        if flagged {
+            if let Some(thinking) = self.thinking.take() {
+                thinking.search.cancel();
+            }
+            self.promotion_choices.clear();
+            self.selected_piece = None;
+            self.valid_squares = vec![];
+            self.flagged = Some(color);
+            self.refresh();
+        }
+    }
+
+    /**
+     * Plays an action from the current position, the actions taken back are dropped unless it
+     * is the next one
+     */
+    fn play(&mut self, action: Action) {
+        // With a clock, the times of the actions taken back no longer hold
+        if self.clock.is_none() && self.redo.last() == Some(&action) {
+            self.redo.pop();
+        } else {
+            self.redo.clear();
+        }
+        if let Some(clock) = &mut self.clock {
+            let remaining = clock.press(self.board.color_turn(), self.turn_start.elapsed());
+            self.clocks.truncate(self.board.history().len());
+            self.clocks.push(remaining);
+            self.turn_start = Instant::now();
+        }
+        self.board
+            .do_move(action)
+            .expect("a legal action could not be played");
+    }
+
+    /**
+     * Moves through the game until the given number of plies is played, keeping the actions
+     * taken back
+     */
+    fn go_to(&mut self, plies: usize) {
+        if plies == self.board.history().len() {
+            return;
+        }
+        if let Some(thinking) = self.thinking.take() {
+            thinking.search.cancel();
+        }
+        self.promotion_choices.clear();
+        while self.board.history().len() > plies {
+            match self.board.undo_last_move() {
+                Some(action) => self.redo.push(action),
+                None => break,
+            }
+        }
+        while self.board.history().len() < plies {
+            match self.redo.pop() {
+                Some(action) => {
+                    if self.board.do_move(action).is_err() {
+                        self.redo.push(action);
+                        break;
+                    }
+                }
+                None => break,
+            }
+        }
+        self.restore_clock();
+        self.refresh();
+        self.engine_reply();
+    }
+
+    fn handle_panel_clicked(&mut self, x: f32, y: f32) {
+        let column = x - 8.0 * SQUARE_SIZE - PANEL_MARGIN - PANEL_NUMBER_WIDTH;
+        if column < 0.0 || y < self.moves_top() {
+            return;
+        }
+        let column = (column / PANEL_MOVE_WIDTH) as usize;
+        let row = ((y - self.moves_top()) / PANEL_LINE_HEIGHT) as usize;
+        if column > 1 || row >= self.visible_rows() {
+            return;
+        }
+        let row = self.first_row() + row;
+        let Some(ply) = (2 * row + column).checked_sub(self.first_slot()) else {
+            return;
+        };
+        if ply < self.move_texts.len() {
+            self.go_to(ply + 1);
+        }
+    }
+
+    /**
+     * Board index of the overlay square showing each promotion choice, from the promotion square
+     * towards the center of the board
+     */
+    fn promotion_squares(&self) -> Vec<usize> {
+        let Some(first) = self.promotion_choices.first() else {
+            return Vec::new();
+        };
+        let target = TO_BOARD[first.target_square()] as usize;
+        (0..self.promotion_choices.len())
+            .map(|i| {
+                if target < 8 {
+                    target + 8 * i
+                } else {
+                    target - 8 * i
+                }
+            })
+            .collect()
+    }
+
+    /**
+     * Plays the promotion under the click, any click outside of the overlay cancels the move
+     */
+    fn pick_promotion(&mut self, position: usize) {
+        let picked = self
+            .promotion_squares()
+            .iter()
+            .position(|&square| square == position)
+            .map(|index| self.promotion_choices[index]);
+        if let Some(action) = picked {
+            self.play(action);
+        }
+        self.promotion_choices.clear();
+        self.refresh();
+        self.engine_reply();
     }
 
     fn handle_mouse_clicked(&mut self, button: MouseButton, x: f32, y: f32) {
         if button != MouseButton::Left {
             return;
         }
+        if x >= 8.0 * SQUARE_SIZE {
+            self.handle_panel_clicked(x, y);
+            return;
+        }
+        if self.status.is_over()
+            || self.flagged.is_some()
+            || self.thinking.is_some()
+            || self.engine == Some(*self.board.color_turn())
+        {
+            return;
+        }
         let x = (x / SQUARE_SIZE) as usize;
         let y = (y / SQUARE_SIZE) as usize;
 
         if x >= 8 || y >= 8 {
             return;
         }
-        let position = x + 8 * y;
-        if let Some(_) = self.board.piece_at_board_index(position) {
+        let position = self.oriented(x + 8 * y);
+        if !self.promotion_choices.is_empty() {
+            self.pick_promotion(position);
+            return;
+        }
+        if self.board.piece_at_board_index(position).is_some() {
             self.selected_piece = Some(position);
             self.valid_squares = actions::generate_moves(&self.board)
                 .iter()
                 .filter(|current_move| current_move.start_square() == TO_MAILBOX[position])
-                .map(Box::as_ref)
-                .map(ChessAction::target_square)
+                .map(Action::target_square)
                 .map(|index| TO_BOARD[index] as usize)
                 .collect();
             println!("{}", self.valid_squares.len())
@@ -143,70 +593,432 @@ impl TetraState {
         let y = (y / SQUARE_SIZE) as usize;
 
         if x >= 8 || y >= 8 {
+            self.selected_piece = None;
+            self.valid_squares = vec![];
             return;
         }
 
-        let position = x + 8 * y;
+        let position = self.oriented(x + 8 * y);
         if let Some(start) = self.selected_piece {
-            if let Some(piece) = self.board.piece_at_board_index(start as usize) {
-                let mut moves = actions::generate_moves(&self.board);
-                let mut selected: Vec<Box<dyn ChessAction>> = Vec::new();
-
-                for i in (0..moves.len()).rev() {
-                    if moves[i].start_square() == TO_MAILBOX[start]
-                        && moves[i].target_square() == TO_MAILBOX[position] as usize
-                    {
-                        selected.push(moves.remove(i))
-                    }
-                }
+            if self.board.piece_at_board_index(start).is_some() {
+                let mut selected: Vec<Action> = actions::generate_moves(&self.board)
+                    .into_iter()
+                    .filter(|action| {
+                        action.start_square() == TO_MAILBOX[start]
+                            && action.target_square() == TO_MAILBOX[position]
+                    })
+                    .collect();
 
-                if !selected.is_empty() {
-                    self.board.do_move(selected.remove(0));
+                if selected.len() > 1 {
+                    // Only promotions share their squares, the piece is picked in the overlay
+                    selected.sort_by_key(promotion_rank);
+                    self.promotion_choices = selected;
+                } else if let Some(action) = selected.pop() {
+                    self.play(action);
                 }
             }
         }
 
-        self.view = self.board_to_displayable();
+        self.refresh();
         self.selected_piece = None;
         self.valid_squares = vec![];
+        self.engine_reply();
     }
 
     fn handle_key_pressed(&mut self, key: Key) {
-        if key == Key::Left {
-            self.board.undo_last_move();
-            self.view = self.board_to_displayable();
+        let played = self.board.history().len();
+        match key {
+            Key::Left => self.go_to(played.saturating_sub(1)),
+            Key::Right if !self.redo.is_empty() => self.go_to(played + 1),
+            Key::Home => self.go_to(0),
+            Key::End if !self.redo.is_empty() => self.go_to(played + self.redo.len()),
+            Key::F => self.flipped = !self.flipped,
+            Key::Space => self.move_now(),
+            Key::S => self.save_game(),
+            _ => (),
         }
     }
-    fn board_to_displayable(&self) -> DisplayableBoard {
-        let mut board = [(); 64].map(|_| {
-            Some(Piece::Pawn {
-                color: Color::WHITE,
-            })
-        });
 
-        for (index, curr) in self.board.iter() {
-            board[TO_BOARD[index] as usize] = match curr {
-                Some(piece) => Some(piece.clone()),
-                None => None,
-            };
+    /**
+     * Starts searching a copy of the board when the engine plays the side to move
+     */
+    fn engine_reply(&mut self) {
+        // The engine waits while an earlier position of the game is shown
+        if self.engine != Some(*self.board.color_turn())
+            || self.status.is_over()
+            || self.thinking.is_some()
+            || !self.redo.is_empty()
+        {
+            return;
+        }
+        let mut limits = self.limits;
+        if let Some(clock) = &self.clock {
+            let remaining = clock.remaining(self.board.color_turn());
+            let budget = protocol::time_for_move(remaining, clock.control.increment, None);
+            limits.movetime = Some(
+                limits
+                    .movetime
+                    .map_or(budget, |movetime| movetime.min(budget)),
+            );
         }
-        DisplayableBoard { board }
+        let (sender, best_move) = mpsc::channel();
+        let search = BackgroundSearch::start(
+            self.board.clone(),
+            limits,
+            false,
+            |_| (),
+            move |_, result| {
+                // The receiver is gone when the window was closed during the search
+                let _ = sender.send(result.best_move);
+            },
+        );
+        self.thinking = Some(EngineSearch { search, best_move });
     }
 
+    /**
+     * Plays the move of the engine once its search has ended
+     */
+    fn poll_engine(&mut self) {
+        let received = match &self.thinking {
+            Some(thinking) => thinking.best_move.try_recv(),
+            None => return,
+        };
+        match received {
+            Ok(best_move) => {
+                if let Some(thinking) = self.thinking.take() {
+                    thinking.search.finish();
+                }
+                self.play_engine_move(best_move);
+            }
+            Err(TryRecvError::Empty) => (),
+            Err(TryRecvError::Disconnected) => self.thinking = None,
+        }
+    }
+
+    /**
+     * Stops the search and plays its best move so far
+     */
+    fn move_now(&mut self) {
+        if let Some(thinking) = self.thinking.take() {
+            thinking.search.finish();
+            if let Ok(best_move) = thinking.best_move.recv() {
+                self.play_engine_move(best_move);
+            }
+        }
+    }
+
+    fn play_engine_move(&mut self, best_move: Option<ActionKey>) {
+        let action = best_move.and_then(|key| actions::generate_moves(&self.board).take(&key));
+        if let Some(action) = action {
+            self.play(action);
+        }
+        self.refresh();
+    }
+
+    /**
+     * Appends the current game to the games archive
+     */
+    fn save_game(&mut self) {
+        let mut board = self.whole_game();
+        let mut game = PgnGame::from_board(&mut board);
+        if let Some(clock) = &self.clock {
+            if let Some(tag) = clock.control.to_pgn_tag() {
+                game.set_tag("TimeControl", &tag);
+            }
+            game.set_clocks(&self.clocks);
+        }
+        if let Some(color) = self.flagged {
+            game.set_result(board.timeout_status(&color).result());
+        }
+        let saved = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(GAMES_FILE)
+            .and_then(|mut file| writeln!(file, "{}", game));
+        match saved {
+            Ok(_) => println!("Game saved to {}", GAMES_FILE),
+            Err(error) => println!("Could not save the game : {}", error),
+        }
+    }
     fn piece_to_texture(&self, piece: &Piece) -> &Texture {
         match piece {
-            Piece::Pawn { color } => &self.asset_from_color(&color).pawn,
-            Piece::Bishop { color } => &self.asset_from_color(&color).bishop,
-            Piece::Knight { color } => &self.asset_from_color(&color).knight,
-            Piece::Rook { color, .. } => &self.asset_from_color(&color).rook,
-            Piece::Queen { color } => &self.asset_from_color(&color).queen,
-            Piece::King { color, .. } => &self.asset_from_color(&color).king,
+            Piece::Pawn { color } => &self.asset_from_color(color).pawn,
+            Piece::Bishop { color } => &self.asset_from_color(color).bishop,
+            Piece::Knight { color } => &self.asset_from_color(color).knight,
+            Piece::Rook { color, .. } => &self.asset_from_color(color).rook,
+            Piece::Queen { color } => &self.asset_from_color(color).queen,
+            Piece::King { color, .. } => &self.asset_from_color(color).king,
+        }
+    }
+}
+
+/**
+ * Order of the promotion choices in the overlay, the queen first
+ */
+fn promotion_rank(action: &Action) -> usize {
+    match action.promotion() {
+        Some(Piece::Queen { .. }) => 0,
+        Some(Piece::Rook { .. }) => 1,
+        Some(Piece::Bishop { .. }) => 2,
+        _ => 3,
+    }
+}
+
+impl TetraState {
+    /**
+     * Darkens the board and shows the promotion choices as a column over the promotion square
+     */
+    fn draw_promotion(&mut self, ctx: &mut Context) -> tetra::Result {
+        let board_size = 8.0 * SQUARE_SIZE;
+        let rect = Rectangle::new(0.0, 0.0, board_size, board_size);
+        Mesh::rectangle(ctx, graphics::mesh::ShapeStyle::Fill, rect)?.draw(
+            ctx,
+            DrawParams::new().color(graphics::Color::rgba(0.0, 0.0, 0.0, 0.6)),
+        );
+        let square = Mesh::rectangle(
+            ctx,
+            graphics::mesh::ShapeStyle::Fill,
+            Rectangle::new(0.0, 0.0, SQUARE_SIZE, SQUARE_SIZE),
+        )?;
+        for (index, action) in self.promotion_squares().iter().zip(&self.promotion_choices) {
+            let (x, y) = (self.x_position(*index), self.y_position(*index));
+            square.draw(
+                ctx,
+                DrawParams::new()
+                    .position(Vec2::new(x, y))
+                    .color(graphics::Color::rgb(0.9, 0.9, 0.9)),
+            );
+            if let Some(piece) = action.promotion() {
+                draw_resize(ctx, self.piece_to_texture(&piece), x, y);
+            }
+        }
+        Ok(())
+    }
+
+    /**
+     * Darkens the board and writes the result of the game in the middle
+     */
+    fn draw_status(&mut self, ctx: &mut Context) -> tetra::Result {
+        let board_size = 8.0 * SQUARE_SIZE;
+        let rect = Rectangle::new(0.0, 0.0, board_size, board_size);
+        Mesh::rectangle(ctx, graphics::mesh::ShapeStyle::Fill, rect)?.draw(
+            ctx,
+            DrawParams::new().color(graphics::Color::rgba(0.0, 0.0, 0.0, 0.6)),
+        );
+        if let Some(bounds) = self.status_text.get_bounds(ctx) {
+            self.status_text.draw(
+                ctx,
+                DrawParams::new().position(Vec2::new(
+                    (board_size - bounds.width) / 2.0,
+                    (board_size - bounds.height) / 2.0,
+                )),
+            );
+        }
+        Ok(())
+    }
+}
+
+impl TetraState {
+    /**
+     * Tints the squares of the last action and makes the king in check glow red
+     */
+    fn draw_highlights(&self, ctx: &mut Context, i: usize) -> tetra::Result {
+        let (x, y) = (self.x_position(i), self.y_position(i));
+        if let Some((start, target)) = self.last_move {
+            if i == start || i == target {
+                let rect = Rectangle::new(0.0, 0.0, SQUARE_SIZE, SQUARE_SIZE);
+                Mesh::rectangle(ctx, graphics::mesh::ShapeStyle::Fill, rect)?.draw(
+                    ctx,
+                    DrawParams::new()
+                        .position(Vec2::new(x, y))
+                        .color(graphics::Color::rgba(0.8, 0.7, 0.0, 0.4)),
+                );
+            }
+        }
+        if self.checked_king == Some(i) {
+            // Stacked circles, the more opaque towards the center
+            let center = Vec2::new(x + SQUARE_SIZE / 2.0, y + SQUARE_SIZE / 2.0);
+            for radius in [0.5, 0.4, 0.3] {
+                Mesh::circle(
+                    ctx,
+                    graphics::mesh::ShapeStyle::Fill,
+                    center,
+                    radius * SQUARE_SIZE,
+                )?
+                .draw(
+                    ctx,
+                    DrawParams::new().color(graphics::Color::rgba(1.0, 0.0, 0.0, 0.3)),
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /**
+     * Ranks in the top left corner of the left column, files in the bottom right corner of the
+     * bottom row
+     */
+    fn draw_coordinates(&mut self, ctx: &mut Context) {
+        let color = graphics::Color::rgba(0.0, 0.0, 0.0, 0.7);
+        for row in 0..8 {
+            // The rank of the square shown on the row, from a8 or h1 in the corner
+            let rank = if self.flipped { row } else { 7 - row };
+            self.rank_labels[rank].draw(
+                ctx,
+                DrawParams::new()
+                    .position(Vec2::new(
+                        COORDINATE_MARGIN,
+                        SQUARE_SIZE * row as f32 + COORDINATE_MARGIN,
+                    ))
+                    .color(color),
+            );
+        }
+        for column in 0..8 {
+            let file = if self.flipped { 7 - column } else { column };
+            let label = &mut self.file_labels[file];
+            let width = label.get_bounds(ctx).map_or(0.0, |bounds| bounds.width);
+            label.draw(
+                ctx,
+                DrawParams::new()
+                    .position(Vec2::new(
+                        SQUARE_SIZE * (column + 1) as f32 - width - COORDINATE_MARGIN,
+                        8.0 * SQUARE_SIZE - COORDINATE_FONT_SIZE - 2.0 * COORDINATE_MARGIN,
+                    ))
+                    .color(color),
+            );
         }
     }
 }
 
+impl TetraState {
+    /**
+     * Move list on the right of the board, the current action highlighted
+     */
+    fn draw_panel(&mut self, ctx: &mut Context) -> tetra::Result {
+        let first_row = self.first_row();
+        let color = graphics::Color::rgb(0.1, 0.1, 0.1);
+        if let Some(position) = self
+            .board
+            .history()
+            .len()
+            .checked_sub(1)
+            .and_then(|ply| self.ply_position(ply))
+        {
+            let rect = Rectangle::new(0.0, 0.0, PANEL_MOVE_WIDTH - 8.0, PANEL_LINE_HEIGHT);
+            Mesh::rectangle(ctx, graphics::mesh::ShapeStyle::Fill, rect)?.draw(
+                ctx,
+                DrawParams::new()
+                    .position(position - Vec2::new(4.0, 2.0))
+                    .color(graphics::Color::rgba(0.8, 0.7, 0.0, 0.4)),
+            );
+        }
+        let (top, visible_rows) = (self.moves_top(), self.visible_rows());
+        for (row, number) in self
+            .number_texts
+            .iter_mut()
+            .enumerate()
+            .skip(first_row)
+            .take(visible_rows)
+        {
+            number.draw(
+                ctx,
+                DrawParams::new()
+                    .position(Vec2::new(
+                        8.0 * SQUARE_SIZE + PANEL_MARGIN,
+                        top + PANEL_LINE_HEIGHT * (row - first_row) as f32,
+                    ))
+                    .color(color),
+            );
+        }
+        for ply in 0..self.move_texts.len() {
+            if let Some(position) = self.ply_position(ply) {
+                self.move_texts[ply].draw(ctx, DrawParams::new().position(position).color(color));
+            }
+        }
+        Ok(())
+    }
+
+    /**
+     * Clocks above and below the move list, the one of the side at the bottom of the board last.
+     * The clock of the side to move is highlighted, a fallen flag is shown in red
+     */
+    fn draw_clocks(&mut self, ctx: &mut Context) -> tetra::Result {
+        let Some(clock) = self.clock else {
+            return Ok(());
+        };
+        let to_move = *self.board.color_turn();
+        let spent = self.turn_start.elapsed();
+        let (top, bottom) = if self.flipped {
+            (Color::WHITE, Color::BLACK)
+        } else {
+            (Color::BLACK, Color::WHITE)
+        };
+        let positions = [
+            (top, PANEL_MARGIN),
+            (bottom, 8.0 * SQUARE_SIZE - PANEL_MARGIN - CLOCK_HEIGHT),
+        ];
+        for (color, y) in positions {
+            let time = if color == to_move && self.is_clock_running() {
+                clock.running(&color, spent)
+            } else if self.flagged == Some(color) && self.redo.is_empty() {
+                Duration::ZERO
+            } else {
+                clock.remaining(&color)
+            };
+            if color == to_move && !self.status.is_over() {
+                let rect = Rectangle::new(
+                    0.0,
+                    0.0,
+                    PANEL_WIDTH - 2.0 * PANEL_MARGIN,
+                    CLOCK_HEIGHT - 8.0,
+                );
+                Mesh::rectangle(ctx, graphics::mesh::ShapeStyle::Fill, rect)?.draw(
+                    ctx,
+                    DrawParams::new()
+                        .position(Vec2::new(8.0 * SQUARE_SIZE + PANEL_MARGIN, y))
+                        .color(graphics::Color::rgba(0.8, 0.7, 0.0, 0.4)),
+                );
+            }
+            let text_color = if time.is_zero() {
+                graphics::Color::rgb(0.8, 0.0, 0.0)
+            } else {
+                graphics::Color::rgb(0.1, 0.1, 0.1)
+            };
+            let text = match color {
+                Color::WHITE => &mut self.clock_texts[0],
+                Color::BLACK => &mut self.clock_texts[1],
+            };
+            text.set_content(format_clock(time));
+            text.draw(
+                ctx,
+                DrawParams::new()
+                    .position(Vec2::new(
+                        8.0 * SQUARE_SIZE + 2.0 * PANEL_MARGIN,
+                        y + (CLOCK_HEIGHT - 8.0 - FONT_SIZE) / 2.0,
+                    ))
+                    .color(text_color),
+            );
+        }
+        Ok(())
+    }
+}
+
+/**
+ * Minutes and seconds, with tenths of a second under ten seconds
+ */
+fn format_clock(time: Duration) -> String {
+    let seconds = time.as_secs();
+    if seconds < 10 {
+        format!("0:{:02}.{}", seconds, time.subsec_millis() / 100)
+    } else {
+        format!("{}:{:02}", seconds / 60, seconds % 60)
+    }
+}
+
 impl State for TetraState {
-    fn update(&mut self, ctx: &mut tetra::Context) -> Result<(), TetraError> {
+    fn update(&mut self, _ctx: &mut tetra::Context) -> Result<(), TetraError> {
+        self.poll_engine();
+        self.check_flag();
         Ok(())
     }
 
@@ -221,45 +1033,31 @@ impl State for TetraState {
             square.draw(
                 ctx,
                 DrawParams::new()
-                    .position(Vec2::new(
-                        TetraState::x_position(i),
-                        TetraState::y_position(i),
-                    ))
+                    .position(Vec2::new(self.x_position(i), self.y_position(i)))
                     .scale(Vec2::new(
-                        SQUARE_SIZE as f32 / square.width() as f32,
-                        SQUARE_SIZE as f32 / square.height() as f32,
+                        SQUARE_SIZE / square.width() as f32,
+                        SQUARE_SIZE / square.height() as f32,
                     )),
             );
-            match self.view.board[i] {
-                Some(piece) => {
-                    if self
-                        .selected_piece
-                        .map(|selected| selected == i)
-                        .unwrap_or(false)
-                    {
-                        continue;
-                    }
-                    let texture = self.piece_to_texture(&piece);
-                    draw_resize(
-                        ctx,
-                        texture,
-                        TetraState::x_position(i),
-                        TetraState::y_position(i),
-                    );
+            self.draw_highlights(ctx, i)?;
+            if let Some(piece) = self.view.board[i] {
+                if self.selected_piece == Some(i) {
+                    continue;
                 }
-                None => (),
+                let texture = self.piece_to_texture(&piece);
+                draw_resize(ctx, texture, self.x_position(i), self.y_position(i));
             }
         }
-        if let Some((ghost, _)) = self.board.double_pawn_move {
-            let ghost = TO_BOARD[ghost];
+        self.draw_coordinates(ctx);
+        self.draw_panel(ctx)?;
+        self.draw_clocks(ctx)?;
+        if let Some((ghost, _)) = self.board.double_pawn_move() {
+            let ghost = TO_BOARD[ghost] as usize;
             let rect = Rectangle::new(0.0, 0.0, SQUARE_SIZE, SQUARE_SIZE);
             Mesh::rectangle(ctx, graphics::mesh::ShapeStyle::Fill, rect)?.draw(
                 ctx,
                 DrawParams::new()
-                    .position(Vec2::new(
-                        SQUARE_SIZE * (ghost % 8) as f32,
-                        SQUARE_SIZE * (ghost / 8) as f32,
-                    ))
+                    .position(Vec2::new(self.x_position(ghost), self.y_position(ghost)))
                     .color(graphics::Color::rgba(0.0, 0.5, 0.0, 0.5)),
             )
         }
@@ -269,25 +1067,28 @@ impl State for TetraState {
                 ctx,
                 DrawParams::new()
                     .position(Vec2::new(
-                        SQUARE_SIZE * (square % 8) as f32,
-                        SQUARE_SIZE * (square / 8) as f32,
+                        self.x_position(*square),
+                        self.y_position(*square),
                     ))
                     .color(graphics::Color::rgba(0.0, 0.0, 0.0, 0.5)),
             )
         }
         if let Some(index) = self.selected_piece {
-            match self.board.piece_at_board_index(index) {
-                Some(piece) => {
-                    let texture = self.piece_to_texture(piece);
-                    draw_resize(
-                        ctx,
-                        texture,
-                        input::get_mouse_x(ctx) - SQUARE_SIZE / 2.0,
-                        input::get_mouse_y(ctx) - SQUARE_SIZE / 2.0,
-                    );
-                }
-                None => (),
-            };
+            if let Some(piece) = self.view.board[index] {
+                let texture = self.piece_to_texture(&piece);
+                draw_resize(
+                    ctx,
+                    texture,
+                    input::get_mouse_x(ctx) - SQUARE_SIZE / 2.0,
+                    input::get_mouse_y(ctx) - SQUARE_SIZE / 2.0,
+                );
+            }
+        }
+        if !self.promotion_choices.is_empty() {
+            self.draw_promotion(ctx)?;
+        }
+        if self.status.is_over() {
+            self.draw_status(ctx)?;
         }
         Ok(())
     }